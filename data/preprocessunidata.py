@@ -24,6 +24,7 @@ URL = ('https://www.unicode.org/Public/UCD/latest/ucdxml/'
 INFILE = os.path.join(tempfile.gettempdir(),
                       os.path.basename(urllib.parse.urlparse(URL).path))
 OUTFILE = 'chardata.txt.gz'
+MARKSFILE = 'combining.txt.gz'
 
 
 def main():
@@ -39,13 +40,20 @@ def main():
         with zinfile.open(xmlfile) as binfile:
             with io.TextIOWrapper(binfile, 'utf-8') as infile:
                 dom = xml.dom.minidom.parse(infile)
-    print(f'writing {OUTFILE} …', flush=True)
-    with gzip.open(OUTFILE, 'wt', encoding='utf-8') as outfile:
+    print(f'writing {OUTFILE} and {MARKSFILE} …', flush=True)
+    with gzip.open(OUTFILE, 'wt', encoding='utf-8') as outfile, \
+            gzip.open(MARKSFILE, 'wt', encoding='utf-8') as marksfile:
         for element in dom.getElementsByTagName('char'):
             try:
                 cp = int(element.getAttribute('cp'), 16)
             except ValueError:
                 continue
+            if element.getAttribute('gc') in {'Mn', 'Mc', 'Me'}:
+                # Combining marks are excluded from the main data so are
+                # written separately for the composer
+                name = element.getAttribute('na').upper()
+                if name:
+                    marksfile.write(f'{cp:X}\t{name}\n')
             if cp < 33:
                 continue
             ws = element.getAttribute('WSpace')
@@ -91,7 +99,8 @@ def main():
                 keywords.add('QUOTE')
             keywords = '\v'.join(sorted(keywords))
             outfile.write(f'{cp:X}\t{name}\t{keywords}\n')
-    print(f'wrote {OUTFILE} • {time.monotonic() - t:.01f} secs', flush=True)
+    print(f'wrote {OUTFILE} and {MARKSFILE} • '
+          f'{time.monotonic() - t:.01f} secs', flush=True)
 
 
 def settle(text):
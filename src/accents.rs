@@ -0,0 +1,126 @@
+// Copyright © 2021-22 Mark Summerfield. All rights reserved.
+// License: GPLv3
+
+use std::collections::HashMap;
+use unicode_normalization::{
+    char::is_combining_mark, UnicodeNormalization,
+};
+
+// Maps base characters to their precomposed variants (e.g., e → é è ê ë …)
+// using the canonical decompositions of the characters in the character
+// data
+#[derive(Clone, Debug, Default)]
+pub struct Accents {
+    variants: HashMap<char, Vec<char>>,
+}
+
+impl Accents {
+    pub fn new(chardata: &str) -> Self {
+        let mut ranked: HashMap<char, Vec<(Vec<usize>, char)>> =
+            HashMap::new();
+        for line in chardata.lines() {
+            let c = match line
+                .split_once('\t')
+                .and_then(|(cp, _)| u32::from_str_radix(cp, 16).ok())
+                .and_then(char::from_u32)
+            {
+                Some(c) => c,
+                None => continue,
+            };
+            let mut decomposed = std::iter::once(c).nfd();
+            if let Some(base) = decomposed.next() {
+                let marks = decomposed.collect::<Vec<char>>();
+                // Skip duplicates like U+1F71 whose NFC form is U+03AC
+                if base != c
+                    && !marks.is_empty()
+                    && std::iter::once(c).nfc().eq(std::iter::once(c))
+                    && marks.iter().all(|mark| is_combining_mark(*mark))
+                {
+                    let rank = marks.iter().map(|mark| mark_rank(*mark));
+                    ranked.entry(base).or_default().push((
+                        std::iter::once(marks.len()).chain(rank).collect(),
+                        c,
+                    ));
+                }
+            }
+        }
+        let variants = ranked
+            .into_iter()
+            .map(|(base, mut variants)| {
+                variants.sort();
+                (base, variants.into_iter().map(|(_, c)| c).collect())
+            })
+            .collect();
+        Self { variants }
+    }
+
+    // Returns the variants most common first; a precomposed base is
+    // treated as its unaccented base (so é gives e's variants)
+    pub fn variants_for(&self, c: char) -> &[char] {
+        let base = std::iter::once(c).nfd().next().unwrap_or(c);
+        match self.variants.get(&base) {
+            Some(variants) => variants,
+            None => &[],
+        }
+    }
+}
+
+// Fewer marks sort first, then the marks most used in Latin-script
+// orthographies
+fn mark_rank(mark: char) -> usize {
+    COMMON_MARKS
+        .iter()
+        .position(|common| *common == mark)
+        .unwrap_or(COMMON_MARKS.len() + mark as usize)
+}
+
+const COMMON_MARKS: [char; 18] = [
+    '\u{0301}', // acute
+    '\u{0300}', // grave
+    '\u{0302}', // circumflex
+    '\u{0308}', // diaeresis
+    '\u{0303}', // tilde
+    '\u{0327}', // cedilla
+    '\u{030A}', // ring above
+    '\u{030C}', // caron
+    '\u{0304}', // macron
+    '\u{0307}', // dot above
+    '\u{0328}', // ogonek
+    '\u{0306}', // breve
+    '\u{030B}', // double acute
+    '\u{0323}', // dot below
+    '\u{0326}', // comma below
+    '\u{031B}', // horn
+    '\u{0309}', // hook above
+    '\u{0331}', // macron below
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    static CHARDATA: &str =
+        "1EC7\tLATIN SMALL LETTER E WITH CIRCUMFLEX AND DOT BELOW
+0113\tLATIN SMALL LETTER E WITH MACRON
+00EB\tLATIN SMALL LETTER E WITH DIAERESIS
+00E9\tLATIN SMALL LETTER E WITH ACUTE
+1EBD\tLATIN SMALL LETTER E WITH TILDE
+00EA\tLATIN SMALL LETTER E WITH CIRCUMFLEX
+00E8\tLATIN SMALL LETTER E WITH GRAVE
+00C9\tLATIN CAPITAL LETTER E WITH ACUTE
+1F71\tGREEK SMALL LETTER ALPHA WITH OXIA
+03AC\tGREEK SMALL LETTER ALPHA WITH TONOS
+0065\tLATIN SMALL LETTER E
+";
+
+    #[test]
+    fn variants_are_most_common_first() {
+        let accents = Accents::new(CHARDATA);
+        let e = ['é', 'è', 'ê', 'ë', 'ẽ', 'ē', 'ệ'];
+        assert_eq!(accents.variants_for('e'), e);
+        assert_eq!(accents.variants_for('é'), e);
+        assert_eq!(accents.variants_for('E'), ['É']);
+        assert_eq!(accents.variants_for('α'), ['\u{03AC}']);
+        assert!(accents.variants_for('x').is_empty());
+    }
+}
@@ -3,56 +3,124 @@
 
 mod actions;
 use super::CONFIG;
-use crate::fixed::Action;
+use crate::accents::Accents;
+use crate::compare_form;
+use crate::composer_form;
+use crate::fancy::Styles;
+use crate::fancy_form;
+use crate::fixed::{Action, Popup};
+use crate::fonts::{self, Fonts};
+use crate::glyph_form;
+use crate::history_form;
 use crate::html_form;
+use crate::inspect_form;
 use crate::main_window;
+use crate::theme::Colors;
+use crate::undo::EditHistory;
 use fltk::{
     app,
-    app::{channel, App, Receiver, Scheme, Sender},
-    browser::HoldBrowser,
+    app::{channel, App, Receiver, Sender},
+    browser::{HoldBrowser, MultiBrowser},
     enums::Font,
     frame::Frame,
-    input::Input,
     menu::MenuButton,
     misc::InputChoice,
     prelude::*,
+    text::TextEditor,
     window::Window,
 };
+use std::cell::RefCell;
+use std::collections::HashSet;
+use std::rc::Rc;
 
 pub struct Application {
     app: App,
     main_window: Window,
     find_combo: InputChoice,
     history_menu_button: MenuButton,
-    browser: HoldBrowser,
+    favorites_menu_button: MenuButton,
+    snippets_menu_button: MenuButton,
+    copy_as_menu_button: MenuButton,
+    transform_menu_button: MenuButton,
+    tools_menu_button: MenuButton,
+    browser: MultiBrowser,
     browser_font_index: usize,
-    copy_input: Input,
+    fonts: Fonts,
+    colors: Colors,
+    output_editor: TextEditor,
+    output_history: Rc<RefCell<EditHistory>>,
+    nfc_frame: Frame,
+    toggled: Option<(i32, char)>,
     preview_frame: Frame,
     help_form: Option<html_form::Form>,
+    compare_form: Option<compare_form::Form>,
+    composer_form: Option<composer_form::Form>,
+    glyph_form: Option<glyph_form::Form>,
+    history_form: Option<history_form::Form>,
+    inspect_form: Option<inspect_form::Form>,
+    fancy_form: Option<fancy_form::Form>,
+    completion_browser: HoldBrowser,
+    completions: Vec<char>, // Those shown in the completion browser
     chardata: Option<String>,
+    code_points: HashSet<u32>, // Those in the character data
+    styles: Option<Styles>,
+    accents: Option<Accents>,
     sender: Sender<Action>,
     receiver: Receiver<Action>,
 }
 
 impl Application {
     pub fn new() -> Self {
-        let app =
-            App::default().with_scheme(Scheme::Gleam).load_system_fonts();
+        let app = App::default().load_system_fonts();
+        let colors = {
+            let config = CONFIG.get().read().unwrap();
+            config.theme.colors()
+        };
+        colors.apply();
         let (sender, receiver) = channel::<Action>();
         let mut widgets = main_window::make(sender);
         main_window::add_event_handlers(&mut widgets.main_window, sender);
+        let output_history =
+            Rc::from(RefCell::from(EditHistory::default()));
+        main_window::add_output_event_handler(
+            &mut widgets.output_editor,
+            widgets.completion_browser.clone(),
+            sender,
+            Rc::clone(&output_history),
+        );
         widgets.main_window.show();
         let mut app = Self {
             app,
             main_window: widgets.main_window,
             find_combo: widgets.find_combo,
             history_menu_button: widgets.history_menu_button,
+            favorites_menu_button: widgets.favorites_menu_button,
+            snippets_menu_button: widgets.snippets_menu_button,
+            copy_as_menu_button: widgets.copy_as_menu_button,
+            transform_menu_button: widgets.transform_menu_button,
+            tools_menu_button: widgets.tools_menu_button,
             browser: widgets.browser,
             browser_font_index: 4, // Courier
-            copy_input: widgets.copy_input,
+            fonts: Fonts::default(),
+            colors,
+            output_editor: widgets.output_editor,
+            output_history,
+            nfc_frame: widgets.nfc_frame,
+            toggled: None,
             preview_frame: widgets.preview_frame,
             help_form: None,
+            compare_form: None,
+            composer_form: None,
+            glyph_form: None,
+            history_form: None,
+            inspect_form: None,
+            fancy_form: None,
+            completion_browser: widgets.completion_browser,
+            completions: vec![],
             chardata: None,
+            code_points: HashSet::new(),
+            styles: None,
+            accents: None,
             sender,
             receiver,
         };
@@ -67,12 +135,23 @@ impl Application {
                     Action::Search => self.on_search(),
                     Action::SearchFor(i) => self.on_search_for(i),
                     Action::Copy => self.on_copy(),
-                    Action::Clear => self.copy_input.set_value(""),
+                    Action::CopyAs(format) => self.on_copy_as(format),
+                    Action::ResultsMenu => self.on_results_menu(),
+                    Action::Clear => self.on_clear(),
                     Action::AddChar(c) => self.on_add_char(c),
-                    Action::AddFromTable => self.on_add_from_table(),
+                    Action::AccentPicker => self.on_accent_picker(),
+                    Action::Complete => self.on_complete(),
+                    Action::AddSelected => self.on_add_selected(),
                     Action::MaybeAddFromTable => {
                         self.on_maybe_add_from_table()
                     }
+                    Action::SelectAll => self.on_select_all(),
+                    Action::SelectChar(c) => self.on_select_char(c),
+                    Action::FocusToSearch => self
+                        .find_combo
+                        .input()
+                        .take_focus()
+                        .unwrap_or_default(),
                     Action::FocusToSearchResults => {
                         self.browser.take_focus().unwrap_or_default()
                     }
@@ -80,10 +159,39 @@ impl Application {
                         self.find_combo.take_focus().unwrap_or_default();
                         self.find_combo.menu_button().popup();
                     }
+                    Action::PopupMenu(popup) => self.on_popup_menu(popup),
                     Action::UpdatePreview => self.on_update_preview(),
+                    Action::OutputChanged => self.on_output_changed(),
+                    Action::Unescape => self.on_unescape(),
+                    Action::Transform(transform) => {
+                        self.on_transform(transform)
+                    }
+                    Action::Fancy(style) => self.on_fancy(style),
+                    Action::FancyPreview => self.on_fancy_preview(),
+                    Action::Favorites => self.on_favorites(),
+                    Action::InsertSnippet(i) => self.on_insert_snippet(i),
+                    Action::SaveSnippet => self.on_save_snippet(),
+                    Action::DeleteSnippet(i) => self.on_delete_snippet(i),
+                    Action::ToggleCodePoint => self.on_toggle_code_point(),
+                    Action::Undo => self.on_undo(),
+                    Action::Redo => self.on_redo(),
+                    Action::RemoveFromHistory(c) => {
+                        self.on_remove_from_history(c)
+                    }
+                    Action::Compare => self.on_compare(),
+                    Action::Composer => self.on_composer(),
+                    Action::InsertComposed(precomposed) => {
+                        self.on_insert_composed(precomposed)
+                    }
+                    Action::GlyphInspector => self.on_glyph_inspector(),
+                    Action::Inspect => self.on_inspect(),
                     Action::Options => self.on_options(),
+                    Action::Export => self.on_export(),
+                    Action::Import => self.on_import(),
                     Action::About => self.on_about(),
                     Action::Help => self.on_help(),
+                    Action::HideCompletion => self.on_hide_completion(),
+                    Action::HistoryPanel => self.on_history_panel(),
                     Action::Quit => self.on_quit(),
                 }
             }
@@ -91,20 +199,14 @@ impl Application {
     }
 
     fn on_startup(&mut self) {
-        if self.find_combo.menu_button().size() > 0 {
-            self.on_search_for(0);
-            let mut input = self.find_combo.input();
-            input.set_position(0).unwrap_or_default();
-            input.set_mark(input.maximum_size()).unwrap_or_default();
-            input.take_focus().unwrap_or_default();
-        }
+        self.fonts = Fonts::new();
         let fonts = app::fonts();
         const INVALID: usize = 99999;
         let mut indexes =
             [INVALID, INVALID, INVALID, INVALID, self.browser_font_index];
         for i in 0..fonts.len() {
             let font = Font::by_index(i);
-            let name = font.get_name().to_uppercase().replace(' ', "");
+            let name = fonts::normalized(&font.get_name());
             let index = match name.as_str() {
                 // Order is most to least preferred
                 "DEJAVUSANSMONO" => 0,
@@ -120,9 +222,20 @@ impl Application {
                 self.browser_font_index = i;
                 let font = Font::by_index(i);
                 self.preview_frame.set_label_font(font);
-                self.copy_input.set_text_font(font);
+                self.output_editor.set_text_font(font);
                 break;
             }
         }
+        // Searched after the fonts are known so that the first results get
+        // their fallback fonts and coverage flags
+        if self.find_combo.menu_button().size() > 0 {
+            self.on_search_for(0);
+            let mut input = self.find_combo.input();
+            input.set_position(0).unwrap_or_default();
+            input.set_mark(input.maximum_size()).unwrap_or_default();
+            input.take_focus().unwrap_or_default();
+        }
+        self.on_output_changed();
+        self.report_config_problems();
     }
 }
@@ -2,18 +2,44 @@
 // License: GPLv3
 
 use super::CONFIG;
-use crate::fixed::{about_html, Action, CHARDATA, HELP_HTML};
+use crate::accents::Accents;
+use crate::blocks;
+use crate::compare_form;
+use crate::composer_form;
+use crate::escape::{self, Format};
+use crate::exchange::{self, Category, Import};
+use crate::exchange_form;
+use crate::fancy::{Style, Styles};
+use crate::fancy_form;
+use crate::favorites_form;
+use crate::fixed::{
+    about_html, Action, Popup, APPNAME, CHARDATA, COMPLETION_MAX,
+    COMPLETION_MIN, COMPLETION_WIDTH, EXCHANGE_FILTER, HELP_HTML,
+    HELP_KEYS_MARKER, MENU_CHARS, PROBLEMS_MAX, SNIPPET_PREVIEW_MAX,
+};
+use crate::glyph_form;
+use crate::history_form;
 use crate::html_form;
+use crate::inspect_form;
 use crate::main_window;
 use crate::options_form;
+use crate::transform::{self, Transform};
+use crate::undo::EditState;
 use crate::util;
 use crate::Application;
 use flate2::read::GzDecoder;
-use fltk::{app, prelude::*};
-use std::collections::HashSet;
+use fltk::{
+    app, dialog,
+    enums::{Color, Font},
+    menu::MenuItem,
+    prelude::*,
+    text::TextBuffer,
+};
+use std::collections::{HashMap, HashSet};
 use std::io::prelude::*;
 use std::iter::Iterator;
 use thousands::Separable;
+use unicode_normalization::char::is_combining_mark;
 
 type WordSet = HashSet<String>;
 
@@ -24,37 +50,72 @@ impl Application {
             return; // nothing to search for
         }
         self.browser.clear();
-        let (cp1, cp2) =
-            self.get_code_points(&all_of.union(&any_of).collect());
-        self.maybe_populate_chardata();
+        let matches = self.find_matches(&all_of, &any_of, &none_of, true);
+        let words = all_of.union(&any_of).collect();
         let mut n = 1;
+        for (c, desc, keywords) in matches {
+            let cp = c as u32;
+            n += 1;
+            let bg = if n % 2 == 0 {
+                format!("@B{}", self.colors.stripe_bg)
+            } else {
+                String::new()
+            };
+            let (font, fg, flag) =
+                match self.fonts.font_for(cp, self.browser_font_index) {
+                    Some(font) => (font, String::new(), ""),
+                    None => (
+                        self.browser_font_index,
+                        format!("@C{}", self.colors.missing_fg),
+                        " (not in any installed font)",
+                    ),
+                };
+            let desc = describe(cp, &desc, &keywords, &words);
+            let cp = util::string_for_codepoint(cp);
+            self.browser.insert(
+                n,
+                &format!("{bg}{fg}@F{font}@.{c}\t{cp}\t{desc}{flag}"),
+            );
+        }
+        self.add_header_line(n - 1);
+    }
+
+    // Returns the characters (with their names and keywords) that match
+    // the search sets in character data order; if code_points is true
+    // words may also be code points (e.g., 2192 or U+2192), otherwise
+    // only names are matched
+    fn find_matches(
+        &mut self,
+        all_of: &WordSet,
+        any_of: &WordSet,
+        none_of: &WordSet,
+        code_points: bool,
+    ) -> Vec<(char, String, WordSet)> {
+        let (cp1, cp2) = if code_points {
+            let words = all_of.union(any_of).collect();
+            self.get_code_points(&words)
+        } else {
+            (0, 0)
+        };
+        self.maybe_populate_chardata();
+        let mut matches = vec![];
         if let Some(chardata) = &self.chardata {
             for line in chardata.lines() {
                 let (cp, desc, keywords) = self.get_unicode_data(line);
                 if (cp != 0 && (cp == cp1 || cp == cp2))
-                    || (keywords.intersection(&none_of).count() == 0
+                    || (keywords.intersection(none_of).count() == 0
                         && (any_of.is_empty()
-                            || keywords.intersection(&any_of).count() > 0)
-                        && keywords.intersection(&all_of).count()
+                            || keywords.intersection(any_of).count() > 0)
+                        && keywords.intersection(all_of).count()
                             == all_of.len())
                 {
                     if let Some(c) = char::from_u32(cp) {
-                        n += 1;
-                        let bg = if n % 2 == 0 { "@B247" } else { "" };
-                        let cp = util::string_for_codepoint(cp);
-                        let desc = desc.to_lowercase();
-                        self.browser.insert(
-                            n,
-                            &format!(
-                                "{bg}@F{}@.{c}\t{cp}\t{desc}",
-                                self.browser_font_index
-                            ),
-                        );
+                        matches.push((c, desc, keywords));
                     }
                 }
             }
         }
-        self.add_header_line(n - 1);
+        matches
     }
 
     fn add_header_line(&mut self, n: i32) {
@@ -63,14 +124,22 @@ impl Application {
             self.browser.insert(
                 1,
                 &format!(
-                    "@C7@B58@F{}@.Char\tU+HHHH\tDescription ({} match{s})",
+                    "@C{}@B{}@F{}@.Char\tU+HHHH\tDescription ({} match{s})",
+                    self.colors.header_fg,
+                    self.colors.header_bg,
                     self.browser_font_index,
                     n.separate_with_commas(),
                 ),
             );
             self.update_searches();
         } else {
-            self.browser.insert(1, "@B3@C1No matches found");
+            self.browser.insert(
+                1,
+                &format!(
+                    "@B{}@C{}No matches found",
+                    self.colors.no_match_bg, self.colors.no_match_fg
+                ),
+            );
         }
     }
 
@@ -80,6 +149,11 @@ impl Application {
             let mut text = String::new();
             gz.read_to_string(&mut text)
                 .expect("failed to read internal Unicode character data");
+            self.code_points = text
+                .lines()
+                .filter_map(|line| line.split('\t').next())
+                .filter_map(|cp| u32::from_str_radix(cp, 16).ok())
+                .collect();
             self.chardata = Some(text);
         }
     }
@@ -116,6 +190,10 @@ impl Application {
             }
             if let Ok(cp) = u32::from_str_radix(word, 16) {
                 cp2 = cp;
+            } else if let Some(cp) = escape::parse_code_point(word)
+                .or_else(|| escape::parse_code_point(&word.to_lowercase()))
+            {
+                cp2 = cp; // e.g., U+2192, \u{2192}, &#x2192;, &rarr;
             }
             if cp1 != 0 && cp2 != 0 {
                 break;
@@ -157,54 +235,749 @@ impl Application {
 
     pub(crate) fn on_update_preview(&mut self) {
         self.preview_frame.set_label("");
+        self.preview_frame.set_tooltip("");
         if let Some(c) = self.get_selected_char() {
-            self.preview_frame.set_label(&c.to_string());
+            let cp = c as u32;
+            let font = self
+                .fonts
+                .font_for(cp, self.browser_font_index)
+                .unwrap_or(self.browser_font_index);
+            self.preview_frame.set_label_font(Font::by_index(font));
+            self.preview_frame.set_label(&util::label_text(&c.to_string()));
+            if self.fonts.has_coverage() {
+                let families = self.fonts.families_for(cp);
+                self.preview_frame.set_tooltip(&if families.is_empty() {
+                    format!("No installed font has U+{cp:04X}")
+                } else {
+                    format!(
+                        "Fonts with U+{cp:04X}: {}",
+                        families.join(", ")
+                    )
+                });
+            }
         }
+        self.update_compare_form();
+        self.update_glyph_form();
     }
 
     fn get_selected_char(&mut self) -> Option<char> {
         if let Some(text) = self.browser.selected_text() {
-            let parts: Vec<&str> = text.split('\t').collect();
-            if !parts.is_empty() {
-                let field = parts[0];
-                if field.ends_with("Char") {
-                    return None; // Title row
-                }
-                if !field.is_empty() {
-                    return field.chars().last();
+            return char_for_row(&text);
+        }
+        None
+    }
+
+    // Returns the characters of every selected row in table order
+    fn get_selected_chars(&mut self) -> Vec<char> {
+        let mut chars = vec![];
+        for line in 1..=self.browser.size() {
+            if self.browser.selected(line) {
+                if let Some(c) =
+                    self.browser.text(line).and_then(|t| char_for_row(&t))
+                {
+                    chars.push(c);
                 }
             }
         }
-        None
+        chars
+    }
+
+    pub(crate) fn on_select_all(&mut self) {
+        for line in 1..=self.browser.size() {
+            let is_char_row =
+                self.browser.text(line).and_then(|t| char_for_row(&t));
+            if is_char_row.is_some() {
+                self.browser.select(line);
+            }
+        }
+        self.browser.redraw();
+        self.on_update_preview();
+    }
+
+    // Selects the character's row, searching for the character first if
+    // it isn't in the current results
+    pub(crate) fn on_select_char(&mut self, c: char) {
+        if !self.select_row_for(c) {
+            self.find_combo.set_value(&format!("U+{:04X}", c as u32));
+            self.on_search();
+            self.select_row_for(c);
+        }
+    }
+
+    fn select_row_for(&mut self, c: char) -> bool {
+        for line in 1..=self.browser.size() {
+            if self.browser.text(line).and_then(|t| char_for_row(&t))
+                == Some(c)
+            {
+                self.browser.deselect_all();
+                self.browser.select(line);
+                self.browser.middle_line(line);
+                self.browser.redraw();
+                self.on_update_preview();
+                return true;
+            }
+        }
+        false
     }
 
     pub(crate) fn on_copy(&mut self) {
-        let text = self.copy_input.value();
+        let text = self.output_text();
+        if !text.is_empty() {
+            let format = {
+                let config = CONFIG.get().read().unwrap();
+                if config.copy_as_default {
+                    config.copy_format
+                } else {
+                    Format::Text
+                }
+            };
+            app::copy(&format.encode(&text));
+        }
+    }
+
+    pub(crate) fn on_copy_as(&mut self, format: Format) {
+        let text = self.output_text();
         if !text.is_empty() {
-            app::copy(&text);
+            app::copy(&format.encode(&text));
+            CONFIG.get().write().unwrap().copy_format = format;
+        }
+    }
+
+    pub(crate) fn on_results_menu(&mut self) {
+        let chars = self.get_selected_chars();
+        if chars.is_empty() {
+            return;
+        }
+        let mut labels = Format::ALL
+            .iter()
+            .map(|format| format!("Copy As {}", format.label()))
+            .collect::<Vec<String>>();
+        let groups = {
+            let config = CONFIG.get().read().unwrap();
+            config
+                .favorites
+                .iter()
+                .map(|(name, _)| name.clone())
+                .collect::<Vec<String>>()
+        };
+        for name in groups.iter() {
+            labels.push(format!(
+                "Add to Favorites “{}”",
+                util::label_text(name)
+            ));
+        }
+        labels.push("Add to New Favorites Group…".to_string());
+        let menu = MenuItem::new(
+            &labels.iter().map(|s| s.as_str()).collect::<Vec<&str>>(),
+        );
+        if let Some(item) = menu.popup(app::event_x(), app::event_y()) {
+            if let Some(label) = item.label() {
+                if let Some(i) = labels.iter().position(|l| *l == label) {
+                    if let Some(format) = Format::ALL.get(i) {
+                        let text = chars.iter().collect::<String>();
+                        app::copy(&format.encode(&text));
+                        CONFIG.get().write().unwrap().copy_format = *format;
+                    } else {
+                        let name =
+                            groups.get(i - Format::ALL.len()).cloned();
+                        self.add_to_favorites(name, &chars);
+                    }
+                }
+            }
+        }
+    }
+
+    // Adds the characters to the named favorites group or to a new group
+    // if there's no name
+    fn add_to_favorites(&mut self, name: Option<String>, chars: &[char]) {
+        let name = match name {
+            Some(name) => name,
+            None => {
+                dialog::message_title(&format!("Favorites — {APPNAME}"));
+                match dialog::input_default("New favorites group", "") {
+                    Some(name) => util::menu_name(&name),
+                    None => return,
+                }
+            }
+        };
+        if name.is_empty() {
+            return;
+        }
+        {
+            let mut config = CONFIG.get().write().unwrap();
+            let i =
+                match config.favorites.iter().position(|(n, _)| *n == name)
+                {
+                    Some(i) => i,
+                    None => {
+                        config.favorites.push((name, vec![]));
+                        config.favorites.len() - 1
+                    }
+                };
+            let group = &mut config.favorites[i].1;
+            for c in chars {
+                if !group.contains(c) {
+                    group.push(*c);
+                }
+            }
+        }
+        main_window::populate_favorites_menu_button(
+            &mut self.favorites_menu_button,
+            self.sender,
+        );
+    }
+
+    pub(crate) fn on_favorites(&mut self) {
+        let form = favorites_form::Form::new(self.browser_font_index);
+        if *form.ok.borrow() {
+            main_window::populate_favorites_menu_button(
+                &mut self.favorites_menu_button,
+                self.sender,
+            );
+        }
+    }
+
+    pub(crate) fn on_insert_snippet(&mut self, i: usize) {
+        let text = {
+            let config = CONFIG.get().read().unwrap();
+            config.snippets.get(i).map(|(_, text)| text.clone())
+        };
+        if let Some(text) = text {
+            self.insert_output(&text);
+        }
+    }
+
+    // Saves the output editor's selected text (or all its text if none is
+    // selected) as a snippet, replacing any existing one with the same name
+    pub(crate) fn on_save_snippet(&mut self) {
+        let text = self.output_selection();
+        if text.is_empty() {
+            return;
+        }
+        let first_line = text.lines().find(|line| !line.trim().is_empty());
+        let suggestion = util::menu_name(
+            &first_line
+                .unwrap_or_default()
+                .chars()
+                .take(SNIPPET_PREVIEW_MAX)
+                .collect::<String>(),
+        );
+        dialog::message_title(&format!("Snippets — {APPNAME}"));
+        let name = match dialog::input_default("Snippet name", &suggestion)
+        {
+            Some(name) => util::menu_name(&name),
+            None => return,
+        };
+        if name.is_empty() {
+            return;
+        }
+        let existing = {
+            let config = CONFIG.get().read().unwrap();
+            config.snippets.iter().position(|(n, _)| *n == name)
+        };
+        if let Some(i) = existing {
+            dialog::message_title(&format!("Snippets — {APPNAME}"));
+            if dialog::choice2_default(
+                &format!("Replace the “{name}” snippet?"),
+                "&Replace",
+                "&Cancel",
+                "",
+            ) != Some(0)
+            {
+                return;
+            }
+            CONFIG.get().write().unwrap().snippets[i].1 = text;
+        } else {
+            CONFIG.get().write().unwrap().snippets.push((name, text));
+        }
+        main_window::populate_snippets_menu_button(
+            &mut self.snippets_menu_button,
+            self.sender,
+        );
+    }
+
+    pub(crate) fn on_delete_snippet(&mut self, i: usize) {
+        let name = {
+            let config = CONFIG.get().read().unwrap();
+            match config.snippets.get(i) {
+                Some((name, _)) => name.clone(),
+                None => return,
+            }
+        };
+        dialog::message_title(&format!("Snippets — {APPNAME}"));
+        if dialog::choice2_default(
+            &format!("Delete the “{name}” snippet?"),
+            "&Delete",
+            "&Cancel",
+            "",
+        ) == Some(0)
+        {
+            CONFIG.get().write().unwrap().snippets.remove(i);
+            main_window::populate_snippets_menu_button(
+                &mut self.snippets_menu_button,
+                self.sender,
+            );
+        }
+    }
+
+    // Pops up the precomposed variants of the character before the cursor
+    // in the output editor (replacing it with the chosen one) or in the
+    // search box (adding the chosen one to the output editor)
+    pub(crate) fn on_accent_picker(&mut self) {
+        let in_editor = self.output_editor.has_focus();
+        let (base, position, x, y) = if in_editor {
+            let position = self.output_editor.insert_position();
+            let text = self
+                .output_buffer()
+                .text_range(0, position)
+                .unwrap_or_default();
+            let (x, y) = self.output_editor.position_to_xy(position);
+            (text.chars().last(), position, x, y)
+        } else {
+            let input = self.find_combo.input();
+            let position = input.position() as usize;
+            let text = self.find_combo.value().unwrap_or_default();
+            let base = text.get(..position).and_then(|t| t.chars().last());
+            let (x, y) = (input.x(), input.y() + input.h());
+            (base, position as i32, x, y)
+        };
+        let base = match base {
+            Some(base) => base,
+            None => return,
+        };
+        self.maybe_populate_accents();
+        let variants = match &self.accents {
+            Some(accents) => accents.variants_for(base).to_vec(),
+            None => return,
+        };
+        if variants.is_empty() {
+            return;
+        }
+        let labels = variants
+            .iter()
+            .zip(MENU_CHARS.iter())
+            .map(|(c, key)| format!("&{key}  {c}"))
+            .collect::<Vec<String>>();
+        let menu = MenuItem::new(
+            &labels.iter().map(|s| s.as_str()).collect::<Vec<&str>>(),
+        );
+        if let Some(item) = menu.popup(x, y) {
+            if let Some(label) = item.label() {
+                if let Some(i) = labels.iter().position(|l| *l == label) {
+                    if in_editor {
+                        // Select the base so that it is replaced
+                        let start = position - base.len_utf8() as i32;
+                        self.output_buffer().select(start, position);
+                    }
+                    self.on_add_char(variants[i]);
+                }
+            }
+        }
+    }
+
+    // Shows the characters matching the completion token (e.g., :rarr or
+    // :right_arrow) before the output editor's cursor as it is typed
+    fn update_completion(&mut self) {
+        let position = self.output_editor.insert_position();
+        let before = self
+            .output_buffer()
+            .text_range(0, position)
+            .unwrap_or_default();
+        let token = match util::completion_token(&before) {
+            Some((_, token))
+                if self.output_editor.has_focus()
+                    && token.chars().count() >= COMPLETION_MIN =>
+            {
+                token.to_string()
+            }
+            _ => {
+                self.completion_browser.hide();
+                return;
+            }
+        };
+        let matches = self.completions_for(&token);
+        if matches.is_empty() {
+            self.completion_browser.hide();
+            return;
+        }
+        self.completion_browser.clear();
+        for (c, desc) in matches.iter() {
+            self.completion_browser
+                .add(&format!("@.{c}  {}", desc.to_lowercase()));
+        }
+        self.completion_browser.select(1);
+        self.completions = matches.iter().map(|(c, _)| *c).collect();
+        self.place_completion_browser(position);
+        self.completion_browser.show();
+        self.completion_browser.redraw();
+    }
+
+    // Returns the characters whose names match the token's words
+    fn completions_for(&mut self, token: &str) -> Vec<(char, String)> {
+        let all_of = token
+            .split(|c| c == '_' || c == '-')
+            .filter(|word| !word.is_empty())
+            .map(|word| word.to_uppercase())
+            .collect::<WordSet>();
+        // Completion is by name so, e.g., :cafe doesn't offer U+CAFE
+        let mut matches = self
+            .find_matches(&all_of, &WordSet::new(), &WordSet::new(), false)
+            .into_iter()
+            .map(|(c, desc, _)| (c, desc))
+            .collect::<Vec<(char, String)>>();
+        // Shortest names first since they're the likeliest to be meant,
+        // e.g., BULLET before BULLET OPERATOR
+        matches.sort_by_key(|(_, desc)| desc.len());
+        if let Some(c) = escape::char_for_entity(token) {
+            matches.retain(|(m, _)| *m != c);
+            let desc = self.names_for(&c.to_string()).remove(&(c as u32));
+            matches.insert(0, (c, desc.unwrap_or_default()));
+        }
+        matches.truncate(COMPLETION_MAX);
+        matches
+    }
+
+    // Below the cursor's line, or above it if there isn't room below
+    fn place_completion_browser(&mut self, position: i32) {
+        let (x, y) = self.output_editor.position_to_xy(position);
+        let line_height = self.output_editor.text_size() + 4;
+        let height = (self.completion_browser.text_size() + 4)
+            * self.completions.len() as i32
+            + 4;
+        let width = COMPLETION_WIDTH.min(self.main_window.w());
+        let x = x.min(self.main_window.w() - width).max(0);
+        let y = if y + line_height + height <= self.main_window.h() {
+            y + line_height
+        } else {
+            (y - height).max(0)
+        };
+        self.completion_browser.resize(x, y, width, height);
+    }
+
+    // Replaces the completion token before the output editor's cursor with
+    // the chosen completion
+    pub(crate) fn on_complete(&mut self) {
+        let line = self.completion_browser.value();
+        self.completion_browser.hide();
+        self.output_editor.take_focus().unwrap_or_default();
+        if line < 1 {
+            return;
+        }
+        let c = match self.completions.get(line as usize - 1) {
+            Some(c) => *c,
+            None => return,
+        };
+        let position = self.output_editor.insert_position();
+        let before = self
+            .output_buffer()
+            .text_range(0, position)
+            .unwrap_or_default();
+        if let Some((start, _)) = util::completion_token(&before) {
+            self.output_buffer().select(start as i32, position);
+            self.on_add_char(c);
+        }
+    }
+
+    // Clicking a completion gives it the focus, so it is only hidden if
+    // the focus has gone elsewhere
+    pub(crate) fn on_hide_completion(&mut self) {
+        if !self.completion_browser.has_focus() {
+            self.completion_browser.hide();
+        }
+    }
+
+    fn maybe_populate_accents(&mut self) {
+        if self.accents.is_none() {
+            self.maybe_populate_chardata();
+            if let Some(chardata) = &self.chardata {
+                self.accents = Some(Accents::new(chardata));
+            }
         }
     }
 
     pub(crate) fn on_add_char(&mut self, c: char) {
         util::add_to_history(c);
+        self.update_history();
+        self.insert_output(&c.to_string());
+    }
+
+    // Refreshes the history menu and panel after the history changes
+    fn update_history(&mut self) {
         main_window::populate_history_menu_button(
             &mut self.history_menu_button,
             self.sender,
         );
-        let mut text = self.copy_input.value();
-        text.push(c);
-        self.copy_input.set_value(&text);
+        if self.history_form.is_some() {
+            let names = self.history_names();
+            if let Some(history_form) = &mut self.history_form {
+                history_form.update(names);
+            }
+        }
     }
 
-    pub(crate) fn on_add_from_table(&mut self) {
-        if let Some(c) = self.get_selected_char() {
-            self.on_add_char(c);
+    pub(crate) fn on_history_panel(&mut self) {
+        let names = self.history_names();
+        if let Some(history_form) = &mut self.history_form {
+            history_form.update(names);
+            history_form.show();
+        } else {
+            self.history_form = Some(history_form::Form::new(
+                self.sender,
+                self.browser_font_index,
+                names,
+            ));
+        }
+    }
+
+    pub(crate) fn on_remove_from_history(&mut self, c: char) {
+        CONFIG.get().write().unwrap().history.remove(c);
+        self.update_history();
+    }
+
+    fn history_names(&mut self) -> HashMap<u32, String> {
+        let text = {
+            let config = CONFIG.get().read().unwrap();
+            config
+                .history
+                .sorted(config.history_order)
+                .iter()
+                .map(|item| item.c)
+                .collect::<String>()
+        };
+        self.names_for(&text)
+    }
+
+    // Inserts the text at the output editor's cursor replacing the
+    // selection if there is one
+    fn insert_output(&mut self, text: &str) {
+        self.record_output();
+        let mut buffer = self.output_buffer();
+        let (start, end) =
+            buffer.selection_position().unwrap_or_else(|| {
+                let position = self.output_editor.insert_position();
+                (position, position)
+            });
+        buffer.replace(start, end, text);
+        buffer.unselect();
+        self.output_editor.set_insert_position(start + text.len() as i32);
+        self.output_editor.show_insert_position();
+    }
+
+    fn output_buffer(&self) -> TextBuffer {
+        self.output_editor.buffer().unwrap()
+    }
+
+    fn output_text(&self) -> String {
+        self.output_buffer().text()
+    }
+
+    fn record_output(&mut self) {
+        self.output_history
+            .borrow_mut()
+            .record(EditState::of(&self.output_editor));
+    }
+
+    pub(crate) fn on_clear(&mut self) {
+        if !self.output_text().is_empty() {
+            self.record_output();
+            self.output_buffer().set_text("");
         }
     }
 
+    pub(crate) fn on_undo(&mut self) {
+        let current = EditState::of(&self.output_editor);
+        let state = self.output_history.borrow_mut().undo(current);
+        if let Some(state) = state {
+            state.restore(&mut self.output_editor);
+        }
+    }
+
+    pub(crate) fn on_redo(&mut self) {
+        let current = EditState::of(&self.output_editor);
+        let state = self.output_history.borrow_mut().redo(current);
+        if let Some(state) = state {
+            state.restore(&mut self.output_editor);
+        }
+    }
+
+    pub(crate) fn on_unescape(&mut self) {
+        let text = self.output_text();
+        let unescaped = escape::unescape(&text);
+        if unescaped != text {
+            self.record_output();
+            self.output_buffer().set_text(&unescaped);
+        }
+    }
+
+    pub(crate) fn on_transform(&mut self, transform: Transform) {
+        let text = self.output_selection();
+        self.replace_output_selection(&text, &transform.apply(&text));
+    }
+
+    pub(crate) fn on_fancy(&mut self, style: Style) {
+        self.maybe_populate_styles();
+        let text = self.output_selection();
+        let fancy = match &self.styles {
+            Some(styles) => styles.apply(style, &text),
+            None => return,
+        };
+        self.replace_output_selection(&text, &fancy);
+    }
+
+    // Returns the selected text or all the text if none is selected
+    fn output_selection(&self) -> String {
+        let buffer = self.output_buffer();
+        match buffer.selection_position() {
+            Some((start, end)) => {
+                buffer.text_range(start, end).unwrap_or_default()
+            }
+            None => buffer.text(),
+        }
+    }
+
+    // Replaces the selected text or all the text if none is selected (and
+    // keeps the replacement selected)
+    fn replace_output_selection(&mut self, text: &str, replacement: &str) {
+        if replacement != text {
+            self.record_output();
+            let mut buffer = self.output_buffer();
+            match buffer.selection_position() {
+                Some((start, end)) => {
+                    buffer.replace(start, end, replacement);
+                    buffer.select(start, start + replacement.len() as i32);
+                }
+                None => buffer.set_text(replacement),
+            }
+        }
+    }
+
+    fn maybe_populate_styles(&mut self) {
+        if self.styles.is_none() {
+            self.maybe_populate_chardata();
+            if let Some(chardata) = &self.chardata {
+                self.styles = Some(Styles::new(chardata));
+            }
+        }
+    }
+
+    pub(crate) fn on_fancy_preview(&mut self) {
+        self.maybe_populate_styles();
+        let text = self.output_selection();
+        if let Some(styles) = &self.styles {
+            if let Some(fancy_form) = &mut self.fancy_form {
+                fancy_form.show();
+                fancy_form.update(styles, &text);
+            } else {
+                self.fancy_form = Some(fancy_form::Form::new(
+                    self.sender,
+                    self.browser_font_index,
+                    styles,
+                    &text,
+                ));
+            }
+        }
+    }
+
+    fn update_fancy_form(&mut self) {
+        if matches!(&self.fancy_form, Some(form) if form.shown()) {
+            let text = self.output_selection();
+            if let (Some(styles), Some(fancy_form)) =
+                (&self.styles, &mut self.fancy_form)
+            {
+                fancy_form.update(styles, &text);
+            }
+        }
+    }
+
+    pub(crate) fn on_output_changed(&mut self) {
+        if transform::is_normalized(&self.output_text()) {
+            self.nfc_frame.set_label("NFC");
+            self.nfc_frame.set_label_color(Color::ForeGround);
+            self.nfc_frame
+                .set_tooltip("The output editor's text is in NFC form");
+        } else {
+            self.nfc_frame.set_label("Not NFC");
+            self.nfc_frame.set_label_color(Color::Red);
+            self.nfc_frame.set_tooltip(
+                "The output editor's text is not in NFC form; use Transform→NFC to normalize it",
+            );
+        }
+        self.nfc_frame.redraw();
+        self.update_compare_form();
+        self.update_inspect_form();
+        self.update_fancy_form();
+        self.update_completion();
+    }
+
+    pub(crate) fn on_add_selected(&mut self) {
+        let chars = self.get_selected_chars();
+        if !chars.is_empty() {
+            for c in chars.iter() {
+                util::add_to_history(*c);
+            }
+            self.update_history();
+            self.insert_output(&chars.iter().collect::<String>());
+        }
+    }
+
+    // Converts the hex digits (or U+HHHH) before the output editor's cursor
+    // into the character, or the character before the cursor into U+HHHH
+    pub(crate) fn on_toggle_code_point(&mut self) {
+        let position = self.output_editor.insert_position();
+        let before = self
+            .output_buffer()
+            .text_range(0, position)
+            .unwrap_or_default();
+        let last = before.chars().last();
+        let just_toggled = matches!((self.toggled, last),
+            (Some((p, c)), Some(last)) if p == position && c == last);
+        self.toggled = None;
+        let (start, replacement) = match escape::code_point_at_end(&before)
+        {
+            Some((start, cp)) if !just_toggled => {
+                match char::from_u32(cp) {
+                    Some(c)
+                        if !escape::is_noncharacter(cp)
+                            && self.is_known(c) =>
+                    {
+                        (start, c.to_string())
+                    }
+                    _ => {
+                        dialog::beep(dialog::BeepType::Error);
+                        return; // surrogate, noncharacter, or unknown
+                    }
+                }
+            }
+            _ => match last {
+                Some(c) => (
+                    before.len() - c.len_utf8(),
+                    format!("U+{:04X}", c as u32),
+                ),
+                None => return,
+            },
+        };
+        self.output_buffer().select(start as i32, position);
+        self.insert_output(&replacement);
+        let mut chars = replacement.chars();
+        if let (Some(c), None) = (chars.next(), chars.next()) {
+            // So that pressing the code point key again converts it back
+            // even if it is a hex digit
+            self.toggled = Some((self.output_editor.insert_position(), c));
+        }
+    }
+
+    // Characters in the character data or that it deliberately excludes
+    fn is_known(&mut self, c: char) -> bool {
+        if c.is_whitespace() || is_combining_mark(c) {
+            return true;
+        }
+        self.maybe_populate_chardata();
+        self.code_points.contains(&(c as u32))
+    }
+
     pub(crate) fn on_maybe_add_from_table(&mut self) {
         if let Some(c) = self.get_selected_char() {
-            let text = self.copy_input.value();
+            let text = self.output_text();
             if !text.ends_with(c) {
                 self.on_add_char(c);
             }
@@ -214,37 +987,384 @@ impl Application {
     pub(crate) fn on_options(&mut self) {
         let form = options_form::Form::default();
         if *form.ok.borrow() {
+            self.colors = {
+                let config = CONFIG.get().read().unwrap();
+                config.theme.colors()
+            };
+            self.colors.apply();
+            self.help_form = None; // So it is recreated in the new colors
+            self.on_search();
             util::populate_find_combo(&mut self.find_combo, self.sender);
-            main_window::populate_history_menu_button(
-                &mut self.history_menu_button,
+            self.update_history();
+        }
+    }
+
+    pub(crate) fn on_export(&mut self) {
+        let form =
+            exchange_form::Form::new("Export", &Category::ALL, false);
+        let categories = form.categories();
+        if !*form.ok.borrow() || categories.is_empty() {
+            return;
+        }
+        let mut chooser = dialog::NativeFileChooser::new(
+            dialog::NativeFileChooserType::BrowseSaveFile,
+        );
+        chooser.set_title(&format!("Export — {APPNAME}"));
+        chooser.set_filter(EXCHANGE_FILTER);
+        chooser
+            .set_preset_file(&format!("{}.json", APPNAME.to_lowercase()));
+        chooser.set_option(dialog::NativeFileChooserOptions::SaveAsConfirm);
+        chooser.show();
+        let filename = chooser.filename();
+        if filename.as_os_str().is_empty() {
+            return;
+        }
+        let result = {
+            let config = CONFIG.get().read().unwrap();
+            exchange::export(&config, &categories, &filename)
+        };
+        if let Err(err) = result {
+            dialog::message_title(&format!("Export — {APPNAME}"));
+            dialog::alert_default(&err);
+        }
+    }
+
+    pub(crate) fn on_import(&mut self) {
+        let mut chooser = dialog::NativeFileChooser::new(
+            dialog::NativeFileChooserType::BrowseFile,
+        );
+        chooser.set_title(&format!("Import — {APPNAME}"));
+        chooser.set_filter(EXCHANGE_FILTER);
+        chooser.show();
+        let filename = chooser.filename();
+        if filename.as_os_str().is_empty() {
+            return;
+        }
+        let import = match Import::load(&filename) {
+            Ok(import) => import,
+            Err(err) => {
+                dialog::message_title(&format!("Import — {APPNAME}"));
+                dialog::alert_default(&err);
+                return;
+            }
+        };
+        let form =
+            exchange_form::Form::new("Import", &import.categories(), true);
+        let categories = form.categories();
+        if !*form.ok.borrow() || categories.is_empty() {
+            return;
+        }
+        import.apply(
+            &mut CONFIG.get().write().unwrap(),
+            &categories,
+            form.merge(),
+        );
+        self.colors = {
+            let config = CONFIG.get().read().unwrap();
+            config.theme.colors()
+        };
+        self.colors.apply();
+        self.help_form = None; // So it is recreated in the new colors
+        util::populate_find_combo(&mut self.find_combo, self.sender);
+        self.update_history();
+        main_window::populate_favorites_menu_button(
+            &mut self.favorites_menu_button,
+            self.sender,
+        );
+        main_window::populate_snippets_menu_button(
+            &mut self.snippets_menu_button,
+            self.sender,
+        );
+    }
+
+    pub(crate) fn on_compare(&mut self) {
+        let c = self.get_selected_char();
+        if let Some(compare_form) = &mut self.compare_form {
+            compare_form.show();
+            compare_form.update(c, &self.output_text());
+        } else {
+            self.compare_form = Some(compare_form::Form::new(
+                &self.fonts,
+                c,
+                &self.output_text(),
+            ));
+        }
+    }
+
+    fn update_compare_form(&mut self) {
+        if self.compare_form.is_some() {
+            let c = self.get_selected_char();
+            let text = self.output_text();
+            if let Some(compare_form) = &mut self.compare_form {
+                compare_form.update(c, &text);
+            }
+        }
+    }
+
+    pub(crate) fn on_glyph_inspector(&mut self) {
+        let c = self.get_selected_char();
+        if let Some(glyph_form) = &mut self.glyph_form {
+            glyph_form.show();
+            glyph_form.update(c);
+        } else {
+            self.glyph_form = Some(glyph_form::Form::new(
+                &self.fonts,
+                self.browser_font_index,
+                c,
+            ));
+        }
+    }
+
+    fn update_glyph_form(&mut self) {
+        if self.glyph_form.is_some() {
+            let c = self.get_selected_char();
+            if let Some(glyph_form) = &mut self.glyph_form {
+                glyph_form.update(c);
+            }
+        }
+    }
+
+    pub(crate) fn on_composer(&mut self) {
+        if let Some(composer_form) = &mut self.composer_form {
+            composer_form.show();
+        } else {
+            let base = self.get_selected_char().unwrap_or('a');
+            self.composer_form = Some(composer_form::Form::new(
                 self.sender,
-            );
+                self.browser_font_index,
+                base,
+            ));
+        }
+    }
+
+    pub(crate) fn on_insert_composed(&mut self, precomposed: bool) {
+        let text = match &self.composer_form {
+            Some(composer_form) => composer_form.composed(precomposed),
+            None => return,
+        };
+        if !text.is_empty() {
+            self.insert_output(&text);
         }
     }
 
+    pub(crate) fn on_inspect(&mut self) {
+        let text = self.output_text();
+        let names = self.names_for(&text);
+        if let Some(inspect_form) = &mut self.inspect_form {
+            inspect_form.show();
+            inspect_form.update(&text, &names);
+        } else {
+            self.inspect_form = Some(inspect_form::Form::new(
+                self.sender,
+                self.browser_font_index,
+                &text,
+                &names,
+            ));
+        }
+    }
+
+    fn update_inspect_form(&mut self) {
+        if matches!(&self.inspect_form, Some(form) if form.shown()) {
+            let text = self.output_text();
+            let names = self.names_for(&text);
+            if let Some(inspect_form) = &mut self.inspect_form {
+                inspect_form.update(&text, &names);
+            }
+        }
+    }
+
+    // Returns the names of the text's characters that are in the
+    // character data
+    fn names_for(&mut self, text: &str) -> HashMap<u32, String> {
+        let cps = text.chars().map(|c| c as u32).collect::<HashSet<u32>>();
+        let mut names = HashMap::new();
+        self.maybe_populate_chardata();
+        if let Some(chardata) = &self.chardata {
+            for line in chardata.lines() {
+                let mut cols = line.split('\t');
+                if let (Some(cp), Some(name)) = (cols.next(), cols.next()) {
+                    if let Ok(cp) = u32::from_str_radix(cp, 16) {
+                        if cps.contains(&cp) {
+                            names.insert(cp, name.to_string());
+                            if names.len() == cps.len() {
+                                break;
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        names
+    }
+
     pub(crate) fn on_about(&mut self) {
-        html_form::Form::new("About", &about_html(), true, 500, 280, false);
+        html_form::Form::new(
+            "About",
+            &self.colors.html(&about_html()),
+            true,
+            500,
+            280,
+            false,
+        );
     }
 
+    pub(crate) fn on_popup_menu(&mut self, popup: Popup) {
+        match popup {
+            Popup::History => self.history_menu_button.popup(),
+            Popup::Favorites => self.favorites_menu_button.popup(),
+            Popup::Snippets => self.snippets_menu_button.popup(),
+            Popup::CopyAs => self.copy_as_menu_button.popup(),
+            Popup::Transform => self.transform_menu_button.popup(),
+            Popup::Tools => self.tools_menu_button.popup(),
+        };
+    }
+
+    // The shortcuts table's key rows come from the active key bindings so
+    // that they always match
     pub(crate) fn on_help(&mut self) {
         if let Some(help_form) = &mut self.help_form {
             help_form.show();
         } else {
+            let rows = {
+                let config = CONFIG.get().read().unwrap();
+                config.keys.help_rows()
+            };
             self.help_form = Some(html_form::Form::new(
-                "Help", HELP_HTML, false, 640, 480, true,
+                "Help",
+                &self
+                    .colors
+                    .html(&HELP_HTML.replace(HELP_KEYS_MARKER, &rows)),
+                false,
+                640,
+                480,
+                true,
             ));
         }
     }
 
     pub(crate) fn on_quit(&mut self) {
-        let config = CONFIG.get().read().unwrap();
-        config.save(
-            self.main_window.x(),
-            self.main_window.y(),
-            self.main_window.width(),
-            self.main_window.height(),
-            &self.copy_input.value(),
-        );
+        let result = {
+            let config = CONFIG.get().read().unwrap();
+            config.save(
+                self.main_window.x(),
+                self.main_window.y(),
+                self.main_window.width(),
+                self.main_window.height(),
+                &self.output_text(),
+            )
+        };
+        if let Err(err) = result {
+            dialog::message_title(&format!("Warning — {APPNAME}"));
+            if dialog::choice2_default(
+                &err,
+                "&Quit Anyway",
+                "&Don't Quit",
+                "",
+            ) != Some(0)
+            {
+                return;
+            }
+        }
         self.app.quit();
     }
+
+    // The problems are only reported once since the configuration file is
+    // rewritten without them when quitting
+    pub(crate) fn report_config_problems(&mut self) {
+        let (filename, problems) = {
+            let mut config = CONFIG.get().write().unwrap();
+            (config.filename.clone(), std::mem::take(&mut config.problems))
+        };
+        if !problems.is_empty() {
+            let mut text = format!(
+                "Some of the configuration in {} couldn't be used:\n",
+                filename.display()
+            );
+            for problem in problems.iter().take(PROBLEMS_MAX) {
+                text.push_str(&format!("\n• {problem}"));
+            }
+            if problems.len() > PROBLEMS_MAX {
+                text.push_str(&format!(
+                    "\n• …and {} more",
+                    problems.len() - PROBLEMS_MAX
+                ));
+            }
+            dialog::message_title(&format!("Configuration — {APPNAME}"));
+            dialog::message_default(&text);
+        }
+    }
+}
+
+// Returns the character shown in a results table row or None for the
+// title and "No matches found" rows
+fn char_for_row(text: &str) -> Option<char> {
+    let (field, _) = text.split_once('\t')?;
+    if field.ends_with("Char") {
+        return None; // Title row
+    }
+    field.chars().last()
+}
+
+// Returns the lowercased description with the words that matched the
+// search words uppercased, and with any matching words that aren't in the
+// name itself noted: those from the block name as such and the rest (from
+// aliases or added by the preprocessor, e.g., QUOTE for QUOTATION) as
+// keywords since the character data doesn't say which they are
+fn describe(
+    cp: u32,
+    desc: &str,
+    keywords: &WordSet,
+    words: &HashSet<&String>,
+) -> String {
+    let matched = words
+        .iter()
+        .filter(|word| keywords.contains(word.as_str()))
+        .map(|word| word.as_str())
+        .collect::<Vec<&str>>();
+    let mut text = String::new();
+    let mut in_name = HashSet::new();
+    for (i, name_word) in desc.split(' ').enumerate() {
+        if i > 0 {
+            text.push(' ');
+        }
+        for (j, part) in name_word.split('-').enumerate() {
+            if j > 0 {
+                text.push('-');
+            }
+            if let Some(word) =
+                matched.iter().find(|word| is_word_match(part, word))
+            {
+                text.push_str(part);
+                in_name.insert(*word);
+            } else {
+                text.push_str(&part.to_lowercase());
+            }
+        }
+    }
+    let mut via = vec![];
+    for word in matched.iter().filter(|word| !in_name.contains(*word)) {
+        // The keywords use abbreviated block names, e.g., SUP for
+        // SUPPLEMENT, so block words are matched by prefix
+        let block = blocks::block_for(cp).filter(|block| {
+            block
+                .to_uppercase()
+                .split(|c| c == ' ' || c == '-')
+                .any(|block_word| block_word.starts_with(word))
+        });
+        via.push(match block {
+            Some(block) => format!("via block: {block}"),
+            None => format!("via keyword: {}", word.to_lowercase()),
+        });
+    }
+    via.sort();
+    via.dedup();
+    if !via.is_empty() {
+        text.push_str(&format!(" ({})", via.join("; ")));
+    }
+    text
+}
+
+// The preprocessor adds e.g. LEFT as a keyword for LEFTWARDS
+fn is_word_match(name_word: &str, word: &str) -> bool {
+    name_word == word || name_word.strip_suffix("WARDS") == Some(word)
 }
@@ -0,0 +1,39 @@
+// Copyright © 2021-22 Mark Summerfield. All rights reserved.
+// License: GPLv3
+
+use crate::fixed::BLOCKS;
+use state::Storage;
+
+type Block = (u32, u32, &'static str);
+
+static BLOCK_RANGES: Storage<Vec<Block>> = Storage::new();
+
+// Returns the name of the Unicode block the code point is in
+pub fn block_for(cp: u32) -> Option<&'static str> {
+    let blocks = BLOCK_RANGES.get_or_set(read_blocks);
+    let i = blocks.partition_point(|(_, end, _)| *end < cp);
+    match blocks.get(i) {
+        Some((start, _, name)) if *start <= cp => Some(name),
+        _ => None,
+    }
+}
+
+// Blocks.txt lines are of the form "0000..007F; Basic Latin"
+fn read_blocks() -> Vec<Block> {
+    BLOCKS
+        .lines()
+        .filter_map(|line| {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                return None;
+            }
+            let (range, name) = line.split_once(';')?;
+            let (start, end) = range.split_once("..")?;
+            Some((
+                u32::from_str_radix(start, 16).ok()?,
+                u32::from_str_radix(end, 16).ok()?,
+                name.trim(),
+            ))
+        })
+        .collect()
+}
@@ -0,0 +1,278 @@
+// Copyright © 2021-22 Mark Summerfield. All rights reserved.
+// License: GPLv3
+
+use super::CONFIG;
+use crate::fixed::{
+    APPNAME, BUTTON_HEIGHT, BUTTON_WIDTH, COMPARE_SIZE_MAX,
+    COMPARE_SIZE_MIN, ICON, PAD,
+};
+use crate::fonts::{self, Fonts};
+use crate::util;
+use fltk::{
+    app,
+    browser::CheckBrowser,
+    button::{Button, CheckButton},
+    enums::{Align, Color, Font, FrameType},
+    frame::Frame,
+    group::{Flex, Pack, Scroll},
+    image::SvgImage,
+    misc::Spinner,
+    prelude::*,
+    window::Window,
+};
+use std::cell::RefCell;
+use std::rc::Rc;
+
+pub struct Form {
+    form: Window,
+    view: Rc<RefCell<View>>,
+}
+
+impl Form {
+    pub fn new(fonts: &Fonts, c: Option<char>, editor_text: &str) -> Self {
+        let mut form = make_form();
+        let mut vbox = Flex::default().size_of_parent().column();
+        vbox.set_margin(PAD);
+        vbox.set_pad(PAD);
+        let mut row = Flex::default().row();
+        row.set_pad(PAD);
+        let (font_browser, font_indexes) = make_font_browser();
+        let mut scroll = Scroll::default();
+        scroll.set_frame(FrameType::DownBox);
+        let pack = Pack::default();
+        pack.end();
+        scroll.end();
+        row.set_size(&font_browser, WIDTH / 3);
+        row.end();
+        let (button_row, size_spinner, editor_check, mut close_button) =
+            make_bottom_row();
+        vbox.set_size(&button_row, BUTTON_HEIGHT);
+        vbox.end();
+        form.end();
+        let view = Rc::from(RefCell::from(View {
+            fonts: fonts.clone(),
+            font_browser,
+            font_indexes,
+            scroll,
+            pack,
+            size_spinner,
+            editor_check,
+            c,
+            editor_text: editor_text.to_string(),
+        }));
+        add_event_handlers(&mut form, &mut close_button, &view);
+        form.show();
+        view.borrow_mut().render();
+        Self { form, view }
+    }
+
+    pub fn show(&mut self) {
+        self.form.show();
+    }
+
+    pub fn update(&mut self, c: Option<char>, editor_text: &str) {
+        if self.form.shown() {
+            let mut view = self.view.borrow_mut();
+            view.c = c;
+            view.editor_text = editor_text.to_string();
+            view.render();
+        }
+    }
+}
+
+impl Drop for Form {
+    fn drop(&mut self) {
+        app::delete_widget(self.form.clone());
+    }
+}
+
+struct View {
+    fonts: Fonts,
+    font_browser: CheckBrowser,
+    font_indexes: Vec<usize>,
+    scroll: Scroll,
+    pack: Pack,
+    size_spinner: Spinner,
+    editor_check: CheckButton,
+    c: Option<char>,
+    editor_text: String,
+}
+
+impl View {
+    fn text(&self) -> String {
+        if self.editor_check.is_checked() {
+            self.editor_text.clone()
+        } else if let Some(c) = self.c {
+            c.to_string()
+        } else {
+            String::new()
+        }
+    }
+
+    fn render(&mut self) {
+        self.pack.clear();
+        let text = self.text();
+        let size = self.size_spinner.value() as i32;
+        let width = self.scroll.w() - (PAD * 4);
+        self.pack.resize(
+            self.scroll.x() + PAD,
+            self.scroll.y() + PAD,
+            width,
+            self.pack.h(),
+        );
+        self.pack.set_spacing(PAD);
+        self.pack.begin();
+        for (i, font) in self.font_indexes.iter().enumerate() {
+            let line = i as i32 + 1;
+            if !self.font_browser.checked(line) {
+                continue;
+            }
+            let name = self.font_browser.text(line).unwrap_or_default();
+            let fallback = !text
+                .chars()
+                .all(|c| self.fonts.font_covers(*font, c as u32));
+            let mut name_frame = Frame::default()
+                .with_size(width, BUTTON_HEIGHT)
+                .with_align(Align::Inside | Align::Left);
+            if fallback {
+                name_frame.set_label(&format!("{name} (fallback)"));
+                name_frame.set_label_color(Color::Red);
+            } else {
+                name_frame.set_label(&name);
+            }
+            let mut text_frame = Frame::default()
+                .with_size(width, size * 3 / 2)
+                .with_align(Align::Inside | Align::Left);
+            text_frame.set_frame(FrameType::FlatBox);
+            text_frame.set_color(Color::BackGround2);
+            text_frame.set_label_font(Font::by_index(*font));
+            text_frame.set_label_size(size);
+            text_frame.set_label(&util::label_text(&text));
+        }
+        self.pack.end();
+        self.scroll.redraw();
+    }
+
+    fn save(&self) {
+        let mut config = CONFIG.get().write().unwrap();
+        config.compare_size = self.size_spinner.value() as i32;
+        config.compare_fonts.clear();
+        for i in 1..=self.font_indexes.len() as i32 {
+            if self.font_browser.checked(i) {
+                if let Some(name) = self.font_browser.text(i) {
+                    config.compare_fonts.push(name);
+                }
+            }
+        }
+    }
+}
+
+fn make_form() -> Window {
+    let image = SvgImage::from_data(ICON).unwrap();
+    let mut form = Window::default()
+        .with_size(WIDTH, HEIGHT)
+        .with_label(&format!("Compare Fonts — {APPNAME}"));
+    if let Some(window) = app::first_window() {
+        form.set_pos(window.x() + 50, window.y() + 100);
+    }
+    form.set_icon(Some(image));
+    form.make_resizable(true);
+    form
+}
+
+fn make_font_browser() -> (CheckBrowser, Vec<usize>) {
+    let mut font_browser = CheckBrowser::default();
+    font_browser.set_tooltip("Check the fonts to compare");
+    let config = CONFIG.get().read().unwrap();
+    let mut font_indexes = vec![];
+    for (name, i) in fonts::regular_fonts() {
+        let checked = if config.compare_fonts.is_empty() {
+            font_indexes.len() < DEFAULT_FONT_COUNT
+        } else {
+            config.compare_fonts.contains(&name)
+        };
+        font_browser.add(&name, checked);
+        font_indexes.push(i);
+    }
+    (font_browser, font_indexes)
+}
+
+fn make_bottom_row() -> (Flex, Spinner, CheckButton, Button) {
+    let mut row = Flex::default().row();
+    row.set_pad(PAD);
+    let mut size_label = Button::default()
+        .with_label("&Size")
+        .with_align(Align::Inside | Align::Right);
+    size_label.set_frame(FrameType::NoBox);
+    size_label.clear_visible_focus();
+    let mut size_spinner = Spinner::default();
+    size_spinner
+        .set_range(COMPARE_SIZE_MIN as f64, COMPARE_SIZE_MAX as f64);
+    size_spinner.set_step(2.0);
+    size_spinner
+        .set_value(CONFIG.get().read().unwrap().compare_size as f64);
+    size_spinner.set_tooltip("The size to show the characters at");
+    size_label.set_callback({
+        let mut size_spinner = size_spinner.clone();
+        move |_| {
+            size_spinner.take_focus().unwrap_or_default();
+        }
+    });
+    let mut editor_check =
+        CheckButton::default().with_label("Use &Output Editor's Text");
+    editor_check.set_tooltip("Compare the output editor's text rather than the selected character");
+    let close_button = Button::default().with_label("&Close");
+    row.set_size(&size_label, BUTTON_WIDTH / 2);
+    row.set_size(&size_spinner, BUTTON_WIDTH);
+    row.set_size(&close_button, BUTTON_WIDTH);
+    row.end();
+    (row, size_spinner, editor_check, close_button)
+}
+
+fn add_event_handlers(
+    form: &mut Window,
+    close_button: &mut Button,
+    view: &Rc<RefCell<View>>,
+) {
+    let mut borrowed = view.borrow_mut();
+    borrowed.font_browser.set_callback({
+        let view = Rc::clone(view);
+        move |_| {
+            let mut view = view.borrow_mut();
+            view.save();
+            view.render();
+        }
+    });
+    borrowed.size_spinner.set_callback({
+        let view = Rc::clone(view);
+        move |_| {
+            let mut view = view.borrow_mut();
+            view.save();
+            view.render();
+        }
+    });
+    borrowed.editor_check.set_callback({
+        let view = Rc::clone(view);
+        move |_| {
+            view.borrow_mut().render();
+        }
+    });
+    form.resize_callback({
+        let view = Rc::clone(view);
+        move |_, _, _, _, _| {
+            if let Ok(mut view) = view.try_borrow_mut() {
+                view.render();
+            }
+        }
+    });
+    close_button.set_callback({
+        let mut form = form.clone();
+        move |_| {
+            form.hide();
+        }
+    });
+}
+
+const WIDTH: i32 = 640;
+const HEIGHT: i32 = 480;
+const DEFAULT_FONT_COUNT: usize = 4;
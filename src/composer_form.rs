@@ -0,0 +1,391 @@
+// Copyright © 2021-22 Mark Summerfield. All rights reserved.
+// License: GPLv3
+
+use crate::fixed::{
+    Action, APPNAME, BUTTON_HEIGHT, BUTTON_WIDTH, COMBINING, ICON, PAD,
+};
+use crate::util;
+use flate2::read::GzDecoder;
+use fltk::{
+    app,
+    app::Sender,
+    browser::HoldBrowser,
+    button::Button,
+    enums::{Align, CallbackTrigger, Font, FrameType},
+    frame::Frame,
+    group::Flex,
+    image::SvgImage,
+    input::Input,
+    prelude::*,
+    window::Window,
+};
+use std::cell::RefCell;
+use std::io::prelude::*;
+use std::rc::Rc;
+use unicode_normalization::UnicodeNormalization;
+
+pub struct Form {
+    form: Window,
+    view: Rc<RefCell<View>>,
+}
+
+impl Form {
+    pub fn new(sender: Sender<Action>, font: usize, base: char) -> Self {
+        let mut form = make_form();
+        let mut vbox = Flex::default().size_of_parent().column();
+        vbox.set_margin(PAD);
+        vbox.set_pad(PAD);
+        let (top_row, base_input, filter_input) = make_top_row(base);
+        vbox.set_size(&top_row, BUTTON_HEIGHT);
+        let mut row = Flex::default().row();
+        row.set_pad(PAD);
+        let mut marks_browser = HoldBrowser::default();
+        marks_browser.set_column_char('\t');
+        marks_browser.set_column_widths(&COLUMN_WIDTHS);
+        marks_browser.set_text_font(Font::by_index(font));
+        marks_browser
+            .set_tooltip("Double-click a combining mark to add it");
+        let mut column = Flex::default().column();
+        column.set_pad(PAD);
+        let mut preview_frame = Frame::default();
+        preview_frame.set_frame(FrameType::DownBox);
+        preview_frame.set_label_font(Font::by_index(font));
+        preview_frame.set_label_size(PREVIEW_SIZE);
+        let mut chosen_browser = HoldBrowser::default();
+        chosen_browser.set_column_char('\t');
+        chosen_browser.set_column_widths(&COLUMN_WIDTHS);
+        chosen_browser.set_text_font(Font::by_index(font));
+        chosen_browser.set_tooltip(
+            "The combining marks in the order they are applied",
+        );
+        let mut info_frame = Frame::default()
+            .with_align(Align::Inside | Align::Left | Align::Wrap);
+        info_frame.set_frame(FrameType::EngravedFrame);
+        column.set_size(&info_frame, BUTTON_HEIGHT * 2);
+        column.end();
+        row.set_size(&column, WIDTH / 2);
+        row.end();
+        let mut buttons = make_bottom_row();
+        vbox.set_size(&buttons.row, BUTTON_HEIGHT);
+        vbox.end();
+        form.end();
+        let view = Rc::from(RefCell::from(View {
+            marks: read_marks(),
+            shown_marks: vec![],
+            chosen: vec![],
+            base_input,
+            filter_input,
+            marks_browser,
+            chosen_browser,
+            preview_frame,
+            info_frame,
+        }));
+        add_event_handlers(&mut form, &mut buttons, &view, sender);
+        form.show();
+        {
+            let mut view = view.borrow_mut();
+            view.filter();
+            view.refresh();
+        }
+        Self { form, view }
+    }
+
+    pub fn show(&mut self) {
+        self.form.show();
+    }
+
+    // Returns the composed text in NFC (precomposed) or NFD (decomposed)
+    // form
+    pub fn composed(&self, precomposed: bool) -> String {
+        let text = self.view.borrow().text();
+        if precomposed {
+            text.nfc().collect()
+        } else {
+            text.nfd().collect()
+        }
+    }
+}
+
+impl Drop for Form {
+    fn drop(&mut self) {
+        app::delete_widget(self.form.clone());
+    }
+}
+
+struct View {
+    marks: Vec<(char, String)>,
+    shown_marks: Vec<char>,
+    chosen: Vec<char>,
+    base_input: Input,
+    filter_input: Input,
+    marks_browser: HoldBrowser,
+    chosen_browser: HoldBrowser,
+    preview_frame: Frame,
+    info_frame: Frame,
+}
+
+impl View {
+    fn text(&self) -> String {
+        let mut text =
+            self.base_input.value().chars().take(1).collect::<String>();
+        text.extend(self.chosen.iter());
+        text
+    }
+
+    // Shows the marks whose names contain every word in the filter
+    fn filter(&mut self) {
+        let words = self
+            .filter_input
+            .value()
+            .split_whitespace()
+            .map(|word| word.to_uppercase())
+            .collect::<Vec<String>>();
+        self.marks_browser.clear();
+        self.shown_marks.clear();
+        for (mark, name) in self.marks.iter() {
+            if words.iter().all(|word| name.contains(word.as_str())) {
+                self.marks_browser.add(&mark_row(*mark, name));
+                self.shown_marks.push(*mark);
+            }
+        }
+    }
+
+    fn add_mark(&mut self) {
+        let line = self.marks_browser.value();
+        if line > 0 {
+            if let Some(mark) = self.shown_marks.get(line as usize - 1) {
+                self.chosen.push(*mark);
+                self.refresh();
+            }
+        }
+    }
+
+    fn remove_mark(&mut self) {
+        let line = self.chosen_browser.value();
+        if line > 0 && line as usize <= self.chosen.len() {
+            self.chosen.remove(line as usize - 1);
+        } else {
+            self.chosen.pop();
+        }
+        self.refresh();
+    }
+
+    fn refresh(&mut self) {
+        self.chosen_browser.clear();
+        for mark in self.chosen.iter() {
+            let name = self
+                .marks
+                .iter()
+                .find(|(c, _)| c == mark)
+                .map(|(_, name)| name.as_str())
+                .unwrap_or_default();
+            self.chosen_browser.add(&mark_row(*mark, name));
+        }
+        let text = self.text();
+        self.preview_frame.set_label(&util::label_text(&text));
+        self.info_frame.set_label(&describe(&text));
+        self.preview_frame.redraw();
+        self.info_frame.redraw();
+    }
+}
+
+struct Buttons {
+    row: Flex,
+    remove_button: Button,
+    clear_button: Button,
+    precomposed_button: Button,
+    decomposed_button: Button,
+    close_button: Button,
+}
+
+fn make_form() -> Window {
+    let image = SvgImage::from_data(ICON).unwrap();
+    let mut form = Window::default()
+        .with_size(WIDTH, HEIGHT)
+        .with_label(&format!("Composer — {APPNAME}"));
+    if let Some(window) = app::first_window() {
+        form.set_pos(window.x() + 50, window.y() + 100);
+    }
+    form.set_icon(Some(image));
+    form.make_resizable(true);
+    form
+}
+
+fn make_top_row(base: char) -> (Flex, Input, Input) {
+    let mut row = Flex::default().row();
+    row.set_pad(PAD);
+    let mut base_label = Button::default()
+        .with_label("&Base")
+        .with_align(Align::Inside | Align::Right);
+    base_label.set_frame(FrameType::NoBox);
+    base_label.clear_visible_focus();
+    let mut base_input = Input::default();
+    base_input.set_value(&base.to_string());
+    base_input.set_trigger(CallbackTrigger::Changed);
+    base_input.set_tooltip("The base character to add the marks to");
+    let mut filter_label = Button::default()
+        .with_label("&Filter")
+        .with_align(Align::Inside | Align::Right);
+    filter_label.set_frame(FrameType::NoBox);
+    filter_label.clear_visible_focus();
+    let mut filter_input = Input::default();
+    filter_input.set_trigger(CallbackTrigger::Changed);
+    filter_input.set_tooltip(
+        "Only show the combining marks whose names have all these words",
+    );
+    base_label.set_callback({
+        let mut base_input = base_input.clone();
+        move |_| {
+            base_input.take_focus().unwrap_or_default();
+        }
+    });
+    filter_label.set_callback({
+        let mut filter_input = filter_input.clone();
+        move |_| {
+            filter_input.take_focus().unwrap_or_default();
+        }
+    });
+    row.set_size(&base_label, BUTTON_WIDTH / 2);
+    row.set_size(&base_input, BUTTON_WIDTH / 2);
+    row.set_size(&filter_label, BUTTON_WIDTH / 2);
+    row.end();
+    (row, base_input, filter_input)
+}
+
+fn make_bottom_row() -> Buttons {
+    let mut row = Flex::default().row();
+    row.set_pad(PAD);
+    let remove_button = Button::default().with_label("&Remove Mark");
+    let clear_button = Button::default().with_label("C&lear Marks");
+    Frame::default();
+    let mut precomposed_button =
+        Button::default().with_label("Insert &Precomposed");
+    precomposed_button
+        .set_tooltip("Add the character to the output editor in NFC form");
+    let mut decomposed_button =
+        Button::default().with_label("Insert &Decomposed");
+    decomposed_button
+        .set_tooltip("Add the character to the output editor in NFD form");
+    let close_button = Button::default().with_label("&Close");
+    row.set_size(&remove_button, BUTTON_WIDTH + PAD * 4);
+    row.set_size(&clear_button, BUTTON_WIDTH + PAD * 4);
+    row.set_size(&precomposed_button, BUTTON_WIDTH + PAD * 8);
+    row.set_size(&decomposed_button, BUTTON_WIDTH + PAD * 8);
+    row.set_size(&close_button, BUTTON_WIDTH);
+    row.end();
+    Buttons {
+        row,
+        remove_button,
+        clear_button,
+        precomposed_button,
+        decomposed_button,
+        close_button,
+    }
+}
+
+fn add_event_handlers(
+    form: &mut Window,
+    buttons: &mut Buttons,
+    view: &Rc<RefCell<View>>,
+    sender: Sender<Action>,
+) {
+    let mut borrowed = view.borrow_mut();
+    borrowed.base_input.set_callback({
+        let view = Rc::clone(view);
+        move |_| {
+            view.borrow_mut().refresh();
+        }
+    });
+    borrowed.filter_input.set_callback({
+        let view = Rc::clone(view);
+        move |_| {
+            view.borrow_mut().filter();
+        }
+    });
+    borrowed.marks_browser.set_callback({
+        let view = Rc::clone(view);
+        move |_| {
+            if app::event_clicks() {
+                view.borrow_mut().add_mark();
+            }
+        }
+    });
+    buttons.remove_button.set_callback({
+        let view = Rc::clone(view);
+        move |_| {
+            view.borrow_mut().remove_mark();
+        }
+    });
+    buttons.clear_button.set_callback({
+        let view = Rc::clone(view);
+        move |_| {
+            let mut view = view.borrow_mut();
+            view.chosen.clear();
+            view.refresh();
+        }
+    });
+    buttons.precomposed_button.set_callback(move |_| {
+        sender.send(Action::InsertComposed(true));
+    });
+    buttons.decomposed_button.set_callback(move |_| {
+        sender.send(Action::InsertComposed(false));
+    });
+    buttons.close_button.set_callback({
+        let mut form = form.clone();
+        move |_| {
+            form.hide();
+        }
+    });
+}
+
+// Returns whether the text has a precomposed (NFC) equivalent
+fn describe(text: &str) -> String {
+    let count = text.chars().count();
+    let nfc = text.nfc().collect::<String>();
+    let nfc_count = nfc.chars().count();
+    let code_points = nfc
+        .chars()
+        .map(|c| format!("U+{:04X}", c as u32))
+        .collect::<Vec<String>>()
+        .join(" ");
+    if count < 2 {
+        "Double-click combining marks to add them to the base".to_string()
+    } else if nfc_count == 1 {
+        format!(
+            "Precomposed equivalent: {} ({code_points})",
+            util::label_text(&nfc)
+        )
+    } else if nfc_count < count {
+        format!("Partly precomposed equivalent: {code_points}")
+    } else {
+        format!("No precomposed equivalent ({code_points})")
+    }
+}
+
+fn mark_row(mark: char, name: &str) -> String {
+    format!(
+        "@.◌{mark}\tU+{:04X}\t{}",
+        mark as u32,
+        name.strip_prefix("COMBINING ").unwrap_or(name).to_lowercase()
+    )
+}
+
+// Lines are of the form "HEX\tNAME"
+fn read_marks() -> Vec<(char, String)> {
+    let mut gz = GzDecoder::new(COMBINING);
+    let mut text = String::new();
+    gz.read_to_string(&mut text)
+        .expect("failed to read internal combining mark data");
+    text.lines()
+        .filter_map(|line| {
+            let (cp, name) = line.split_once('\t')?;
+            let mark = char::from_u32(u32::from_str_radix(cp, 16).ok()?)?;
+            Some((mark, name.to_string()))
+        })
+        .collect()
+}
+
+const WIDTH: i32 = 720;
+const HEIGHT: i32 = 480;
+const PREVIEW_SIZE: i32 = 72;
+const COLUMN_WIDTHS: [i32; 2] = [50, 80];
@@ -1,14 +1,28 @@
 // Copyright © 2021-22 Mark Summerfield. All rights reserved.
 // License: GPLv3
 
+use crate::escape::{self, Format};
 use crate::fixed::{
-    APPNAME, AUTO_MENU_SIZE, SCALE_MAX, SCALE_MIN, WINDOW_HEIGHT_MIN,
-    WINDOW_WIDTH_MIN,
+    APPNAME, AUTO_MENU_SIZE, COMPARE_SIZE_DEFAULT, COMPARE_SIZE_MAX,
+    COMPARE_SIZE_MIN, COMPLETION_PREFIX_DEFAULT, GLYPH_SIZE_DEFAULT,
+    GLYPH_SIZE_MAX, GLYPH_SIZE_MIN, SCALE_MAX, SCALE_MIN,
+    WINDOW_HEIGHT_MIN, WINDOW_WIDTH_MIN,
 };
+use crate::history::{History, HistoryItem, HistoryOrder};
+use crate::keys::KeyBindings;
+use crate::theme::Theme;
 use crate::util;
-use fltk::{app, dialog};
+use chrono::prelude::*;
+use fltk::app;
 use ini::Ini;
-use std::{collections::VecDeque, env, iter::Iterator, path::PathBuf};
+use std::{
+    collections::VecDeque,
+    env, fmt, fs, io,
+    iter::Iterator,
+    path::{Path, PathBuf},
+    str, thread,
+    time::Duration,
+};
 
 #[derive(Clone, Debug)]
 pub struct Config {
@@ -17,70 +31,242 @@ pub struct Config {
     pub window_height: i32,
     pub window_width: i32,
     pub window_scale: f32,
+    pub theme: Theme,
     pub filename: PathBuf,
     pub searches: VecDeque<String>,
     pub searches_size: usize,
-    pub history: VecDeque<char>,
+    pub searches_pinned: usize,
+    pub history: History,
     pub history_size: usize,
+    pub history_order: HistoryOrder,
+    pub favorites: Vec<(String, Vec<char>)>,
+    pub snippets: Vec<(String, String)>,
     pub copy_text: String,
+    pub copy_format: Format,
+    pub copy_as_default: bool,
+    pub completion_prefix: String,
+    pub compare_fonts: Vec<String>,
+    pub compare_size: i32,
+    pub glyph_font: String,
+    pub glyph_size: i32,
+    pub lock_file: bool,
+    pub keys: KeyBindings,
+    // What was read at startup so that saving only overwrites what this
+    // instance changed
+    loaded: Shared,
+    // The problems found reading the configuration file (if any)
+    pub problems: Vec<String>,
 }
 
 impl Config {
-    pub fn new() -> Self {
+    // The filename is the one given on the command line (if any)
+    pub fn new(filename: Option<PathBuf>) -> Self {
         let mut config = Config {
-            filename: get_config_filename(),
+            filename: get_config_filename(filename),
             ..Default::default()
         };
-        if let Ok(ini) = Ini::load_from_file(&config.filename) {
-            if let Some(properties) = ini.section(Some(WINDOW_SECTION)) {
-                read_window_properties(properties, &mut config);
-            }
-            if let Some(properties) = ini.section(Some(GENERAL_SECTION)) {
-                read_general_properties(properties, &mut config);
-            }
+        match Ini::load_from_file(&config.filename) {
+            Ok(mut ini) => config.read(&mut ini),
+            // There's no file on the first run
+            Err(ini::Error::Io(err))
+                if err.kind() == io::ErrorKind::NotFound => {}
+            Err(err) => config.back_up(&err.to_string()),
+        }
+        if !util::isone32(config.window_scale) {
+            app::set_screen_scale(0, config.window_scale);
         }
+        config.loaded = Shared {
+            searches: config.searches.clone(),
+            history: config.history.clone(),
+            favorites: config.favorites.clone(),
+            snippets: config.snippets.clone(),
+            copy_text: config.copy_text.clone(),
+        };
         config
     }
 
+    fn read(&mut self, ini: &mut Ini) {
+        migrate(ini, &mut self.problems);
+        if let Some(properties) = ini.section(Some(WINDOW_SECTION)) {
+            read_window_properties(properties, self);
+        }
+        if let Some(properties) = ini.section(Some(GENERAL_SECTION)) {
+            read_general_properties(properties, self);
+        }
+        if let Some(properties) = ini.section(Some(FAVORITES_SECTION)) {
+            read_favorites(properties, self);
+        }
+        if let Some(properties) = ini.section(Some(SNIPPETS_SECTION)) {
+            read_snippets(properties, self);
+        }
+        if let Some(properties) = ini.section(Some(KEYS_SECTION)) {
+            self.keys = KeyBindings::from_config(
+                properties,
+                KEYS_SECTION,
+                &mut self.problems,
+            );
+        }
+    }
+
+    // Copies a configuration file that can't be read so that it isn't
+    // lost when the defaults are saved over it
+    fn back_up(&mut self, err: &str) {
+        let backup = with_suffix(
+            &self.filename,
+            &format!(".{}.bad", Local::now().format("%Y%m%d-%H%M%S")),
+        );
+        self.problems.push(match fs::copy(&self.filename, &backup) {
+            Ok(_) => format!(
+                "the file couldn't be read ({err}) so the defaults are being used; it has been backed up as {}",
+                backup.display()
+            ),
+            Err(backup_err) => format!(
+                "the file couldn't be read ({err}) so the defaults are being used; it couldn't be backed up ({backup_err}) and will be overwritten"
+            ),
+        });
+    }
+
+    // Returns the value as a number or the default if it isn't valid
+    fn get_num<T>(
+        &mut self,
+        key: &str,
+        value: &str,
+        minimum: T,
+        maximum: T,
+        default: T,
+    ) -> T
+    where
+        T: num::Num + PartialOrd + Copy + str::FromStr + fmt::Display,
+    {
+        match util::get_num(value, minimum, maximum) {
+            Ok(n) => n,
+            Err(err) => {
+                self.problems
+                    .push(format!("{key}: {err}; using {default}"));
+                default
+            }
+        }
+    }
+
+    // Returns the name's value or None (having noted the problem) if it
+    // isn't valid
+    fn get_named<T>(
+        &mut self,
+        key: &str,
+        value: &str,
+        from_name: fn(&str) -> Option<T>,
+    ) -> Option<T> {
+        let named = from_name(value);
+        if named.is_none() {
+            self.problems.push(format!("{key}: “{value}” isn't valid"));
+        }
+        named
+    }
+
     pub fn save(
         &self,
         x: i32,
         y: i32,
         width: i32,
         height: i32,
-        copy_input: &str,
-    ) {
+        copy_text: &str,
+    ) -> Result<(), String> {
         if self.filename.to_string_lossy() == "" {
-            self.warning("failed to save configuration: no filename");
+            Err("failed to save configuration: no filename".to_string())
         } else {
-            let mut ini = Ini::new();
-            ini.with_section(Some(WINDOW_SECTION))
-                .set(X_KEY, x.to_string())
-                .set(Y_KEY, y.to_string())
-                .set(WIDTH_KEY, width.to_string())
-                .set(HEIGHT_KEY, height.to_string())
-                .set(SCALE_KEY, app::screen_scale(0).to_string());
-            ini.with_section(Some(GENERAL_SECTION))
-                .set(HISTORY_KEY, self.history_str())
-                .set(HISTORY_SIZE_KEY, self.history_size.to_string())
-                .set(SEARCH_SIZE_KEY, self.searches_size.to_string())
-                .set(COPY_TEXT_KEY, copy_input);
-            self.save_searches(&mut ini);
-            match ini.write_to_file(&self.filename) {
-                Ok(_) => {}
-                Err(err) => self.warning(&format!(
-                    "failed to save configuration: {err}"
-                )),
+            let _lock = if self.lock_file {
+                Some(LockFile::new(&self.filename)?)
+            } else {
+                None
+            };
+            let mut config = self.clone();
+            config.copy_text = copy_text.to_string();
+            if let Some(theirs) = self.on_disk() {
+                config.merge(&theirs);
             }
+            config.write(x, y, width, height)
         }
     }
 
-    fn history_str(&self) -> String {
-        let mut history = String::new();
-        for c in self.history.iter() {
-            history.push(*c);
+    // Returns the configuration as saved by another instance (or by this
+    // one) or None if there isn't one or it can't be read
+    fn on_disk(&self) -> Option<Config> {
+        let mut ini = Ini::load_from_file(&self.filename).ok()?;
+        let mut config = Config {
+            filename: self.filename.clone(),
+            ..Default::default()
+        };
+        config.read(&mut ini);
+        Some(config)
+    }
+
+    // Merges in the changes another instance saved after this one loaded
+    // its configuration; anything this instance changed takes precedence,
+    // as do its window geometry and settings since it is the last writer
+    fn merge(&mut self, theirs: &Config) {
+        self.history.merge_changes(&self.loaded.history, &theirs.history);
+        // Searches the other instance added are more recent than the ones
+        // this instance loaded so they go first after any pinned ones
+        let pinned = self.searches_pinned.min(self.searches.len());
+        for search in theirs.searches.iter().rev() {
+            if !self.loaded.searches.contains(search)
+                && !self.searches.contains(search)
+            {
+                self.searches.insert(pinned, search.clone());
+            }
+        }
+        self.searches.truncate(self.searches_size.max(pinned));
+        if self.favorites == self.loaded.favorites {
+            self.favorites = theirs.favorites.clone();
+        }
+        if self.snippets == self.loaded.snippets {
+            self.snippets = theirs.snippets.clone();
         }
-        history
+        if self.copy_text == self.loaded.copy_text {
+            self.copy_text = theirs.copy_text.clone();
+        }
+    }
+
+    fn write(
+        &self,
+        x: i32,
+        y: i32,
+        width: i32,
+        height: i32,
+    ) -> Result<(), String> {
+        let mut ini = Ini::new();
+        ini.with_section(Some(WINDOW_SECTION))
+            .set(X_KEY, x.to_string())
+            .set(Y_KEY, y.to_string())
+            .set(WIDTH_KEY, width.to_string())
+            .set(HEIGHT_KEY, height.to_string())
+            .set(SCALE_KEY, app::screen_scale(0).to_string())
+            .set(THEME_KEY, self.theme.name());
+        ini.with_section(Some(GENERAL_SECTION))
+            .set(VERSION_KEY, CONFIG_VERSION.to_string())
+            .set(HISTORY_ITEMS_KEY, self.history.to_config())
+            .set(HISTORY_SIZE_KEY, self.history_size.to_string())
+            .set(HISTORY_ORDER_KEY, self.history_order.name())
+            .set(SEARCH_SIZE_KEY, self.searches_size.to_string())
+            .set(SEARCHES_PINNED_KEY, self.searches_pinned.to_string())
+            .set(COPY_TEXT_KEY, &self.copy_text)
+            .set(COPY_FORMAT_KEY, self.copy_format.name())
+            .set(COPY_AS_DEFAULT_KEY, self.copy_as_default.to_string())
+            .set(COMPLETION_PREFIX_KEY, &self.completion_prefix)
+            .set(
+                COMPARE_FONTS_KEY,
+                self.compare_fonts.join(COMPARE_FONTS_SEPARATOR),
+            )
+            .set(COMPARE_SIZE_KEY, self.compare_size.to_string())
+            .set(GLYPH_FONT_KEY, &self.glyph_font)
+            .set(GLYPH_SIZE_KEY, self.glyph_size.to_string())
+            .set(LOCK_FILE_KEY, self.lock_file.to_string());
+        self.save_searches(&mut ini);
+        self.save_favorites(&mut ini);
+        self.save_snippets(&mut ini);
+        self.save_keys(&mut ini);
+        write_atomically(&ini, &self.filename)
+            .map_err(|err| format!("failed to save configuration: {err}"))
     }
 
     fn save_searches(&self, ini: &mut Ini) {
@@ -90,9 +276,41 @@ impl Config {
         }
     }
 
-    fn warning(&self, message: &str) {
-        dialog::message_title(&format!("Warning — {APPNAME}"));
-        dialog::message(util::x() - 200, util::y() - 100, message);
+    // Each group is saved as a pair of numbered keys (its name and its
+    // characters) so that the groups keep their order; the size is saved
+    // even when zero so that deleting every group doesn't bring back the
+    // default groups
+    fn save_favorites(&self, ini: &mut Ini) {
+        let mut section = ini.with_section(Some(FAVORITES_SECTION));
+        section.set(FAVORITES_SIZE_KEY, self.favorites.len().to_string());
+        for (i, (name, chars)) in self.favorites.iter().enumerate() {
+            section.set(format!("{GROUP_KEY}{}", i + 1), name).set(
+                format!("{CHARS_KEY}{}", i + 1),
+                chars.iter().collect::<String>(),
+            );
+        }
+    }
+
+    // Saved like the favorites; the texts are encoded so that leading and
+    // trailing spaces, tabs, newlines, and invisible characters survive
+    fn save_snippets(&self, ini: &mut Ini) {
+        let mut section = ini.with_section(Some(SNIPPETS_SECTION));
+        section.set(SNIPPETS_SIZE_KEY, self.snippets.len().to_string());
+        for (i, (name, text)) in self.snippets.iter().enumerate() {
+            section.set(format!("{NAME_KEY}{}", i + 1), name).set(
+                format!("{TEXT_KEY}{}", i + 1),
+                escape::encode_snippet(text),
+            );
+        }
+    }
+
+    // Every binding is saved (empty if it is unbound) so that users can
+    // see what can be changed
+    fn save_keys(&self, ini: &mut Ini) {
+        let mut section = ini.with_section(Some(KEYS_SECTION));
+        for (name, combos) in self.keys.to_config() {
+            section.set(name, combos);
+        }
     }
 }
 
@@ -108,9 +326,30 @@ static DEFAULT_SEARCHES: [&str; 9] = [
     "feed",
 ];
 
+static DEFAULT_FAVORITES: [(&str, &str); 3] = [
+    ("Typography", "•…–—‘’“”«»§¶†‡©®™"),
+    ("Arrows", "←→↑↓↔↕⇐⇒⇔"),
+    ("Math", "±×÷−≠≈≤≥∞√∑∏∫∂°"),
+];
+
+static DEFAULT_SNIPPETS: [(&str, &str); 3] =
+    [("Arrow", "→ "), ("Single Quotes", "‘’"), ("Double Quotes", "“”")];
+
 const DEFAULT_HISTORY: [char; 9] =
     ['•', '…', '—', '←', '→', '↑', '↓', '€', '£'];
 
+pub fn default_searches() -> VecDeque<String> {
+    VecDeque::from(DEFAULT_SEARCHES.map(|s| s.to_string()))
+}
+
+pub fn default_history() -> History {
+    let mut history = History::default();
+    for c in DEFAULT_HISTORY.iter() {
+        history.push(HistoryItem::new(*c));
+    }
+    history
+}
+
 impl Default for Config {
     fn default() -> Self {
         Self {
@@ -119,19 +358,55 @@ impl Default for Config {
             window_height: WINDOW_HEIGHT_MIN,
             window_width: WINDOW_WIDTH_MIN,
             window_scale: 1.0,
+            theme: Theme::default(),
             filename: PathBuf::new(),
-            searches: VecDeque::from(
-                DEFAULT_SEARCHES.map(|s| s.to_string()),
-            ),
+            searches: default_searches(),
             searches_size: AUTO_MENU_SIZE,
-            history: VecDeque::from(DEFAULT_HISTORY),
+            searches_pinned: 0,
+            history: default_history(),
             history_size: AUTO_MENU_SIZE,
+            history_order: HistoryOrder::default(),
+            favorites: DEFAULT_FAVORITES
+                .iter()
+                .map(|(name, chars)| {
+                    (name.to_string(), chars.chars().collect())
+                })
+                .collect(),
+            snippets: DEFAULT_SNIPPETS
+                .iter()
+                .map(|(name, text)| (name.to_string(), text.to_string()))
+                .collect(),
             copy_text: String::new(),
+            copy_format: Format::default(),
+            copy_as_default: false,
+            completion_prefix: COMPLETION_PREFIX_DEFAULT.to_string(),
+            compare_fonts: vec![],
+            compare_size: COMPARE_SIZE_DEFAULT,
+            glyph_font: String::new(),
+            glyph_size: GLYPH_SIZE_DEFAULT,
+            lock_file: true,
+            keys: KeyBindings::default(),
+            loaded: Shared::default(),
+            problems: vec![],
         }
     }
 }
 
-fn get_config_filename() -> PathBuf {
+// The command line's filename takes precedence, then the environment
+// variable's, then a configuration file next to the executable (portable
+// mode, e.g., for running from a USB stick), then the usual one
+fn get_config_filename(filename: Option<PathBuf>) -> PathBuf {
+    if let Some(filename) = filename {
+        return filename;
+    }
+    if let Some(filename) = env::var_os(CONFIG_ENV_VAR) {
+        if !filename.is_empty() {
+            return PathBuf::from(filename);
+        }
+    }
+    if let Some(filename) = portable_config_filename() {
+        return filename;
+    }
     let mut dir = dirs::config_dir();
     let mut dot = "";
     if dir.is_none() {
@@ -147,6 +422,148 @@ fn get_config_filename() -> PathBuf {
     }
 }
 
+fn portable_config_filename() -> Option<PathBuf> {
+    let exe = env::current_exe().ok()?;
+    let filename =
+        exe.parent()?.join(format!("{}.ini", APPNAME.to_lowercase()));
+    if filename.is_file() {
+        Some(filename)
+    } else {
+        None
+    }
+}
+
+// Returns the filename with the suffix appended (rather than replacing its
+// extension, since a user-chosen filename may not have the usual one)
+fn with_suffix(filename: &Path, suffix: &str) -> PathBuf {
+    let mut filename = filename.as_os_str().to_os_string();
+    filename.push(suffix);
+    PathBuf::from(filename)
+}
+
+// The parts of the configuration that other instances may also change
+#[derive(Clone, Debug, Default)]
+struct Shared {
+    searches: VecDeque<String>,
+    history: History,
+    favorites: Vec<(String, Vec<char>)>,
+    snippets: Vec<(String, String)>,
+    copy_text: String,
+}
+
+// Held while saving so that instances saving at the same time can't lose
+// each other's changes; it is deleted when dropped
+struct LockFile {
+    filename: PathBuf,
+}
+
+impl LockFile {
+    fn new(filename: &Path) -> Result<Self, String> {
+        let filename = with_suffix(filename, ".lock");
+        for _ in 0..LOCK_TRIES {
+            match fs::OpenOptions::new()
+                .write(true)
+                .create_new(true)
+                .open(&filename)
+            {
+                Ok(_) => return Ok(Self { filename }),
+                Err(err) if err.kind() == io::ErrorKind::AlreadyExists => {
+                    if is_stale(&filename) {
+                        // Left behind by an instance that crashed
+                        fs::remove_file(&filename).unwrap_or_default();
+                    } else {
+                        thread::sleep(Duration::from_millis(LOCK_WAIT_MS));
+                    }
+                }
+                Err(err) => {
+                    return Err(format!(
+                        "failed to lock configuration: {err}"
+                    ))
+                }
+            }
+        }
+        Err(format!(
+            "failed to save configuration: {} is locked by another instance",
+            filename.display()
+        ))
+    }
+}
+
+impl Drop for LockFile {
+    fn drop(&mut self) {
+        fs::remove_file(&self.filename).unwrap_or_default();
+    }
+}
+
+fn is_stale(filename: &Path) -> bool {
+    fs::metadata(filename)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+        .and_then(|modified| modified.elapsed().ok())
+        .is_some_and(|age| age > Duration::from_secs(LOCK_STALE_SECS))
+}
+
+// The file is written to a temporary file which only replaces the old one
+// once it is complete, so a crash while saving can't leave it truncated
+fn write_atomically(ini: &Ini, filename: &Path) -> io::Result<()> {
+    let temp_filename = with_suffix(filename, ".tmp");
+    {
+        let mut file = fs::File::create(&temp_filename)?;
+        ini.write_to(&mut file)?;
+        file.sync_all()?;
+    }
+    fs::rename(&temp_filename, filename)
+}
+
+// Brings an older configuration up to date so that the readers only have
+// to handle the current keys
+fn migrate(ini: &mut Ini, problems: &mut Vec<String>) {
+    let version = match ini.get_from(Some(GENERAL_SECTION), VERSION_KEY) {
+        Some(value) => match util::get_num(value, 1, u32::MAX) {
+            Ok(version) => version,
+            Err(err) => {
+                problems.push(format!(
+                    "{VERSION_KEY}: {err}; using {CONFIG_VERSION}"
+                ));
+                CONFIG_VERSION
+            }
+        },
+        None => 1, // Older versions didn't save a version
+    };
+    if version > CONFIG_VERSION {
+        problems.push(format!("{VERSION_KEY}: the file is from a newer version of {APPNAME} ({version}); anything this version doesn't know about will be lost when it is saved"));
+    }
+    if version < 2 {
+        // Version 1 only saved the history's characters, most recent first
+        if let Some(value) =
+            ini.delete_from(Some(GENERAL_SECTION), HISTORY_KEY)
+        {
+            if ini
+                .get_from(Some(GENERAL_SECTION), HISTORY_ITEMS_KEY)
+                .is_none()
+            {
+                let mut history = History::default();
+                for c in value.chars() {
+                    history.push(HistoryItem::new(c));
+                }
+                ini.with_section(Some(GENERAL_SECTION))
+                    .set(HISTORY_ITEMS_KEY, history.to_config());
+            }
+        }
+    }
+    if version < 3 {
+        // Versions 1 and 2 separated the compare fonts with commas, which
+        // font family names may contain
+        if let Some(value) =
+            ini.get_from(Some(GENERAL_SECTION), COMPARE_FONTS_KEY)
+        {
+            let value = value.replace(',', COMPARE_FONTS_SEPARATOR);
+            ini.with_section(Some(GENERAL_SECTION))
+                .set(COMPARE_FONTS_KEY, value);
+        }
+    }
+}
+
 fn read_window_properties(
     properties: &ini::Properties,
     config: &mut Config,
@@ -154,25 +571,44 @@ fn read_window_properties(
     let max_x = (app::screen_size().0 - 100.0) as i32;
     let max_y = (app::screen_size().1 - 100.0) as i32;
     if let Some(value) = properties.get(X_KEY) {
-        config.window_x = util::get_num(value, 0, max_x, config.window_x)
+        config.window_x =
+            config.get_num(X_KEY, value, 0, max_x, config.window_x)
     }
     if let Some(value) = properties.get(Y_KEY) {
-        config.window_y = util::get_num(value, 0, max_y, config.window_y)
+        config.window_y =
+            config.get_num(Y_KEY, value, 0, max_y, config.window_y)
     }
     if let Some(value) = properties.get(WIDTH_KEY) {
-        config.window_width =
-            util::get_num(value, 200, max_x, config.window_width)
+        config.window_width = config.get_num(
+            WIDTH_KEY,
+            value,
+            200,
+            max_x,
+            config.window_width,
+        )
     }
     if let Some(value) = properties.get(HEIGHT_KEY) {
-        config.window_height =
-            util::get_num(value, 240, max_y, config.window_height)
+        config.window_height = config.get_num(
+            HEIGHT_KEY,
+            value,
+            240,
+            max_y,
+            config.window_height,
+        )
     }
     if let Some(value) = properties.get(SCALE_KEY) {
-        config.window_scale =
-            util::get_num(value, SCALE_MIN, SCALE_MAX, config.window_scale);
-        if !util::isone32(config.window_scale) {
-            app::set_screen_scale(0, config.window_scale);
-        }
+        config.window_scale = config.get_num(
+            SCALE_KEY,
+            value,
+            SCALE_MIN,
+            SCALE_MAX,
+            config.window_scale,
+        );
+    }
+    if let Some(value) = properties.get(THEME_KEY) {
+        config.theme = config
+            .get_named(THEME_KEY, value, Theme::from_name)
+            .unwrap_or(config.theme);
     }
 }
 
@@ -183,37 +619,81 @@ fn read_general_properties(
     if let Some(value) = properties.get(COPY_TEXT_KEY) {
         config.copy_text = value.to_string();
     }
+    if let Some(value) = properties.get(COPY_FORMAT_KEY) {
+        config.copy_format = config
+            .get_named(COPY_FORMAT_KEY, value, Format::from_name)
+            .unwrap_or(config.copy_format);
+    }
+    if let Some(value) = properties.get(COPY_AS_DEFAULT_KEY) {
+        config.copy_as_default = config
+            .get_named(COPY_AS_DEFAULT_KEY, value, |value| {
+                value.parse().ok()
+            })
+            .unwrap_or(config.copy_as_default);
+    }
+    if let Some(value) = properties.get(COMPLETION_PREFIX_KEY) {
+        config.completion_prefix = value.to_string();
+    }
+    if let Some(value) = properties.get(LOCK_FILE_KEY) {
+        config.lock_file = config
+            .get_named(LOCK_FILE_KEY, value, |value| value.parse().ok())
+            .unwrap_or(config.lock_file);
+    }
+    if let Some(value) = properties.get(COMPARE_FONTS_KEY) {
+        config.compare_fonts = value
+            .split(COMPARE_FONTS_SEPARATOR)
+            .filter(|name| !name.is_empty())
+            .map(|name| name.to_string())
+            .collect();
+    }
+    if let Some(value) = properties.get(COMPARE_SIZE_KEY) {
+        config.compare_size = config.get_num(
+            COMPARE_SIZE_KEY,
+            value,
+            COMPARE_SIZE_MIN,
+            COMPARE_SIZE_MAX,
+            config.compare_size,
+        )
+    }
+    if let Some(value) = properties.get(GLYPH_FONT_KEY) {
+        config.glyph_font = value.to_string();
+    }
+    if let Some(value) = properties.get(GLYPH_SIZE_KEY) {
+        config.glyph_size = config.get_num(
+            GLYPH_SIZE_KEY,
+            value,
+            GLYPH_SIZE_MIN,
+            GLYPH_SIZE_MAX,
+            config.glyph_size,
+        )
+    }
     if let Some(value) = properties.get(HISTORY_SIZE_KEY) {
-        config.history_size =
-            util::get_num(value, 2, AUTO_MENU_SIZE, config.history_size)
-    }
-    config.history.clear();
-    if let Some(value) = properties.get(HISTORY_KEY) {
-        if !value.is_empty() {
-            for c in value.chars() {
-                if !config.history.contains(&c) {
-                    config.history.push_back(c);
-                    if config.history.len() == AUTO_MENU_SIZE {
-                        break;
-                    }
-                }
-            }
-        }
+        config.history_size = config.get_num(
+            HISTORY_SIZE_KEY,
+            value,
+            2,
+            AUTO_MENU_SIZE,
+            config.history_size,
+        )
     }
-    if config.history.len() < AUTO_MENU_SIZE {
-        for c in DEFAULT_HISTORY.iter() {
-            if !config.history.contains(c) {
-                config.history.push_back(*c);
-                if config.history.len() == AUTO_MENU_SIZE {
-                    break;
-                }
-            }
-        }
+    if let Some(value) = properties.get(HISTORY_ORDER_KEY) {
+        config.history_order = config
+            .get_named(HISTORY_ORDER_KEY, value, HistoryOrder::from_name)
+            .unwrap_or(config.history_order);
+    }
+    if let Some(value) = properties.get(HISTORY_ITEMS_KEY) {
+        config.history = History::from_config(value);
     }
     if let Some(value) = properties.get(SEARCH_SIZE_KEY) {
-        config.searches_size =
-            util::get_num(value, 2, AUTO_MENU_SIZE, config.searches_size)
+        config.searches_size = config.get_num(
+            SEARCH_SIZE_KEY,
+            value,
+            2,
+            AUTO_MENU_SIZE,
+            config.searches_size,
+        )
     }
+    let searches = config.searches.clone();
     config.searches.clear();
     for i in 1..=AUTO_MENU_SIZE {
         let key = format!("{SEARCH_KEY}{i}");
@@ -224,28 +704,278 @@ fn read_general_properties(
             }
         }
     }
-    if config.searches.len() < AUTO_MENU_SIZE {
-        for s in DEFAULT_SEARCHES.iter() {
-            let s = s.to_string();
-            if !config.searches.contains(&s) {
-                config.searches.push_back(s);
-                if config.searches.len() == AUTO_MENU_SIZE {
-                    break;
+    // Only use the defaults if none were saved so that deleted ones don't
+    // come back
+    if config.searches.is_empty() {
+        config.searches = searches;
+    }
+    if let Some(value) = properties.get(SEARCHES_PINNED_KEY) {
+        config.searches_pinned = config.get_num(
+            SEARCHES_PINNED_KEY,
+            value,
+            0,
+            config.searches.len(),
+            0,
+        )
+    }
+}
+
+fn read_favorites(properties: &ini::Properties, config: &mut Config) {
+    let size = match properties.get(FAVORITES_SIZE_KEY) {
+        Some(value) => config.get_num(
+            &format!("{FAVORITES_SECTION} {FAVORITES_SIZE_KEY}"),
+            value,
+            0,
+            usize::MAX,
+            0,
+        ),
+        None => 0,
+    };
+    config.favorites.clear();
+    for i in 1..=size {
+        if let Some(name) = properties.get(&format!("{GROUP_KEY}{i}")) {
+            let name = name.trim();
+            if name.is_empty()
+                || config.favorites.iter().any(|(n, _)| n == name)
+            {
+                continue;
+            }
+            let mut chars = vec![];
+            if let Some(value) = properties.get(&format!("{CHARS_KEY}{i}"))
+            {
+                for c in value.chars() {
+                    if !c.is_whitespace() && !chars.contains(&c) {
+                        chars.push(c);
+                    }
                 }
             }
+            config.favorites.push((name.to_string(), chars));
         }
     }
 }
 
+fn read_snippets(properties: &ini::Properties, config: &mut Config) {
+    let size = match properties.get(SNIPPETS_SIZE_KEY) {
+        Some(value) => config.get_num(
+            &format!("{SNIPPETS_SECTION} {SNIPPETS_SIZE_KEY}"),
+            value,
+            0,
+            usize::MAX,
+            0,
+        ),
+        None => 0,
+    };
+    config.snippets.clear();
+    for i in 1..=size {
+        if let (Some(name), Some(text)) = (
+            properties.get(&format!("{NAME_KEY}{i}")),
+            properties.get(&format!("{TEXT_KEY}{i}")),
+        ) {
+            let name = name.trim();
+            let text = escape::decode_snippet(text);
+            if !name.is_empty()
+                && !text.is_empty()
+                && !config.snippets.iter().any(|(n, _)| n == name)
+            {
+                config.snippets.push((name.to_string(), text));
+            }
+        }
+    }
+}
+
+const LOCK_TRIES: usize = 30;
+const LOCK_WAIT_MS: u64 = 100;
+const LOCK_STALE_SECS: u64 = 10;
+
+// 1 for files without a version; 2 added history-items; 3 separates the
+// compare-fonts with tabs
+const CONFIG_VERSION: u32 = 3;
+
+pub static CONFIG_ENV_VAR: &str = "CHARFIND_CONFIG";
+
 static WINDOW_SECTION: &str = "Window";
 static X_KEY: &str = "x";
 static Y_KEY: &str = "y";
 static WIDTH_KEY: &str = "width";
 static HEIGHT_KEY: &str = "height";
 static SCALE_KEY: &str = "scale";
+static THEME_KEY: &str = "theme";
 static GENERAL_SECTION: &str = "General";
+static VERSION_KEY: &str = "config-version";
 static HISTORY_KEY: &str = "history";
+static HISTORY_ITEMS_KEY: &str = "history-items";
 static HISTORY_SIZE_KEY: &str = "history-size";
+static HISTORY_ORDER_KEY: &str = "history-order";
 static SEARCH_KEY: &str = "search";
 static SEARCH_SIZE_KEY: &str = "search-size";
+static SEARCHES_PINNED_KEY: &str = "searches-pinned";
 static COPY_TEXT_KEY: &str = "copy";
+static COPY_FORMAT_KEY: &str = "copy-format";
+static COPY_AS_DEFAULT_KEY: &str = "copy-as-default";
+static COMPLETION_PREFIX_KEY: &str = "completion-prefix";
+static COMPARE_FONTS_KEY: &str = "compare-fonts";
+static COMPARE_FONTS_SEPARATOR: &str = "\t"; // Not in font names
+static COMPARE_SIZE_KEY: &str = "compare-size";
+static GLYPH_FONT_KEY: &str = "glyph-font";
+static GLYPH_SIZE_KEY: &str = "glyph-size";
+static LOCK_FILE_KEY: &str = "lock-file";
+static FAVORITES_SECTION: &str = "Favorites";
+static FAVORITES_SIZE_KEY: &str = "size";
+static GROUP_KEY: &str = "group";
+static CHARS_KEY: &str = "chars";
+static SNIPPETS_SECTION: &str = "Snippets";
+static SNIPPETS_SIZE_KEY: &str = "size";
+static NAME_KEY: &str = "name";
+static TEXT_KEY: &str = "text";
+static KEYS_SECTION: &str = "Keys";
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn migrated(text: &str) -> (Ini, Vec<String>) {
+        let mut ini = Ini::load_from_str(text).unwrap();
+        let mut problems = vec![];
+        migrate(&mut ini, &mut problems);
+        (ini, problems)
+    }
+
+    fn general<'a>(ini: &'a Ini, key: &str) -> Option<&'a str> {
+        ini.get_from(Some(GENERAL_SECTION), key)
+    }
+
+    #[test]
+    fn migrate_version_1_history() {
+        let (ini, problems) = migrated("[General]\nhistory=→é\n");
+        assert!(problems.is_empty());
+        assert_eq!(general(&ini, HISTORY_KEY), None);
+        assert_eq!(
+            general(&ini, HISTORY_ITEMS_KEY),
+            Some("2192:0:0,E9:0:0")
+        );
+    }
+
+    #[test]
+    fn migrate_keeps_existing_history_items() {
+        let (ini, _) =
+            migrated("[General]\nhistory=→é\nhistory-items=41:2:9\n");
+        assert_eq!(general(&ini, HISTORY_KEY), None);
+        assert_eq!(general(&ini, HISTORY_ITEMS_KEY), Some("41:2:9"));
+    }
+
+    #[test]
+    fn migrate_compare_fonts_to_tabs() {
+        let (ini, _) = migrated(
+            "[General]\nconfig-version=2\ncompare-fonts=Serif,Sans\n",
+        );
+        assert_eq!(general(&ini, COMPARE_FONTS_KEY), Some("Serif\tSans"));
+        let (ini, _) = migrated(
+            "[General]\nconfig-version=3\ncompare-fonts=Serif,Sans\n",
+        );
+        assert_eq!(general(&ini, COMPARE_FONTS_KEY), Some("Serif,Sans"));
+    }
+
+    #[test]
+    fn migrate_newer_version() {
+        let (ini, problems) =
+            migrated("[General]\nconfig-version=99\nhistory=→\n");
+        assert_eq!(problems.len(), 1);
+        assert!(problems[0].contains("newer version"));
+        assert_eq!(general(&ini, HISTORY_KEY), Some("→"));
+    }
+
+    #[test]
+    fn migrate_bad_version() {
+        let (ini, problems) = migrated(
+            "[General]\nconfig-version=x\ncompare-fonts=Serif,Sans\n",
+        );
+        assert_eq!(problems.len(), 1);
+        assert!(problems[0].starts_with("config-version: "));
+        assert!(problems[0].ends_with(&format!("using {CONFIG_VERSION}")));
+        assert_eq!(general(&ini, COMPARE_FONTS_KEY), Some("Serif,Sans"));
+    }
+
+    // As if just loaded, i.e., with no changes made by this instance
+    fn loaded(searches: &[&str], pinned: usize) -> Config {
+        let mut config = Config {
+            searches: searches.iter().map(|s| s.to_string()).collect(),
+            searches_pinned: pinned,
+            ..Default::default()
+        };
+        config.loaded = Shared {
+            searches: config.searches.clone(),
+            history: config.history.clone(),
+            favorites: config.favorites.clone(),
+            snippets: config.snippets.clone(),
+            copy_text: config.copy_text.clone(),
+        };
+        config
+    }
+
+    #[test]
+    fn merge_searches() {
+        let mut config = loaded(&["old"], 0);
+        config.searches.push_front("mine".to_string());
+        let theirs = loaded(&["theirs", "old"], 0);
+        config.merge(&theirs);
+        assert_eq!(config.searches, ["theirs", "mine", "old"]);
+        config.searches_size = 2;
+        config.merge(&loaded(&["newest", "theirs"], 0));
+        assert_eq!(config.searches, ["newest", "theirs"]);
+    }
+
+    #[test]
+    fn merge_searches_after_the_pinned() {
+        let mut config = loaded(&["pinned", "old"], 1);
+        config.searches_size = 2;
+        config.merge(&loaded(&["theirs", "pinned", "old"], 0));
+        assert_eq!(config.searches, ["pinned", "theirs"]);
+        assert_eq!(config.searches_pinned, 1);
+    }
+
+    #[test]
+    fn merge_favorites_and_snippets() {
+        let mut theirs = loaded(&[], 0);
+        theirs.favorites = vec![("Theirs".to_string(), vec!['→'])];
+        theirs.snippets = vec![("Theirs".to_string(), "→".to_string())];
+        let mut config = loaded(&[], 0);
+        config.merge(&theirs);
+        assert_eq!(config.favorites, theirs.favorites);
+        assert_eq!(config.snippets, theirs.snippets);
+        let mut config = loaded(&[], 0);
+        config.favorites = vec![("Mine".to_string(), vec!['←'])];
+        config.snippets.clear();
+        config.merge(&theirs);
+        assert_eq!(config.favorites, [("Mine".to_string(), vec!['←'])]);
+        assert!(config.snippets.is_empty());
+    }
+
+    #[test]
+    fn snippets_survive_the_file() {
+        let snippets = [
+            ("quoted", "\"hi\" there"),
+            ("tis", "'tis"),
+            ("tab", "\tx\ty"),
+            ("lines", "one\ntwo\r\n"),
+            ("zwj", "👩\u{200D}💻"),
+            ("arrow", "  → "),
+            ("path", "C:\\dev\\u{41}"),
+        ]
+        .iter()
+        .map(|(name, text)| (name.to_string(), text.to_string()))
+        .collect();
+        let config = Config { snippets, ..Default::default() };
+        let mut ini = Ini::new();
+        config.save_snippets(&mut ini);
+        let mut text = vec![];
+        ini.write_to(&mut text).unwrap();
+        let ini =
+            Ini::load_from_str(&String::from_utf8(text).unwrap()).unwrap();
+        let mut copy = Config::default();
+        read_snippets(
+            ini.section(Some(SNIPPETS_SECTION)).unwrap(),
+            &mut copy,
+        );
+        assert_eq!(copy.snippets, config.snippets);
+    }
+}
@@ -0,0 +1,848 @@
+// Copyright © 2021-22 Mark Summerfield. All rights reserved.
+// License: GPLv3
+
+// Converts text to and from the escaped forms used in source code and
+// markup, e.g., → as \u{2192}, &rarr;, &#x2192;, \2192, %E2%86%92, U+2192
+
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum Format {
+    #[default]
+    Text,
+    Rust,
+    HtmlName,
+    HtmlHex,
+    Css,
+    Url,
+    CodePoint,
+    Latex,
+}
+
+impl Format {
+    pub const ALL: [Format; 8] = [
+        Format::Text,
+        Format::Rust,
+        Format::HtmlName,
+        Format::HtmlHex,
+        Format::Css,
+        Format::Url,
+        Format::CodePoint,
+        Format::Latex,
+    ];
+
+    // The name used in the configuration file
+    pub fn name(&self) -> &'static str {
+        match self {
+            Format::Text => "text",
+            Format::Rust => "rust",
+            Format::HtmlName => "html-name",
+            Format::HtmlHex => "html-hex",
+            Format::Css => "css",
+            Format::Url => "url",
+            Format::CodePoint => "code-point",
+            Format::Latex => "latex",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Format> {
+        Format::ALL.iter().find(|format| format.name() == name).copied()
+    }
+
+    // The menu item text
+    pub fn label(&self) -> &'static str {
+        match self {
+            Format::Text => "Plain &Text",
+            Format::Rust => "&Rust Escapes",
+            Format::HtmlName => "&HTML Entities",
+            Format::HtmlHex => "HTML &Numeric Entities",
+            Format::Css => "&CSS Escapes",
+            Format::Url => "&URL (Percent) Encoding",
+            Format::CodePoint => "Code &Points (U+HHHH)",
+            Format::Latex => "&LaTeX",
+        }
+    }
+
+    pub fn encode(&self, text: &str) -> String {
+        let mut result = String::new();
+        for c in text.chars() {
+            let cp = c as u32;
+            match self {
+                Format::Text => result.push(c),
+                Format::Rust => match c {
+                    '\\' | '"' | '\'' => {
+                        result.push('\\');
+                        result.push(c);
+                    }
+                    ' '..='~' => result.push(c),
+                    _ => result.push_str(&format!("\\u{{{cp:X}}}")),
+                },
+                Format::HtmlName => match entity_for(c) {
+                    Some(name) if !c.is_ascii() || "&<>\"".contains(c) => {
+                        result.push_str(&format!("&{name};"))
+                    }
+                    _ if c.is_ascii() => result.push(c),
+                    _ => result.push_str(&format!("&#x{cp:X};")),
+                },
+                Format::HtmlHex => match c {
+                    '&' | '<' | '>' | '"' => {
+                        result.push_str(&format!("&#x{cp:X};"))
+                    }
+                    ' '..='~' => result.push(c),
+                    _ => result.push_str(&format!("&#x{cp:X};")),
+                },
+                Format::Css => match c {
+                    ' '..='~' if c != '\\' => result.push(c),
+                    _ => {
+                        // A space terminates the escape so that
+                        // unescape() recognizes it and a following hex
+                        // digit isn't taken as part of it
+                        result.push_str(&format!("\\{cp:X} "));
+                    }
+                },
+                Format::Url => {
+                    if c.is_ascii_alphanumeric() || "-._~".contains(c) {
+                        result.push(c);
+                    } else {
+                        let mut buffer = [0; 4];
+                        for byte in c.encode_utf8(&mut buffer).bytes() {
+                            result.push_str(&format!("%{byte:02X}"));
+                        }
+                    }
+                }
+                Format::CodePoint => {
+                    if !result.is_empty() {
+                        result.push(' ');
+                    }
+                    result.push_str(&format!("U+{cp:04X}"));
+                }
+                Format::Latex => match latex_for(c) {
+                    Some(command) => result.push_str(command),
+                    None if c.is_ascii() => result.push(c),
+                    None => {
+                        result.push_str(&format!("\\symbol{{\"{cp:X}}}"))
+                    }
+                },
+            }
+        }
+        result
+    }
+}
+
+// Returns the text with every recognized escape or entity replaced by the
+// character(s) it represents; any other text is left as it is
+pub fn unescape(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut rest = text;
+    let mut word_start = true;
+    let mut word = 0; // Where the current whitespace-separated word starts
+    while let Some(c) = rest.chars().next() {
+        let in_path = is_path(&text[word..text.len() - rest.len()]);
+        if let Some((unescaped, size)) =
+            scan_escape(rest, word_start, in_path)
+        {
+            result.push_str(&unescaped);
+            if rest[..size].ends_with(' ') {
+                word = text.len() - rest.len() + size; // CSS's terminator
+            }
+            rest = &rest[size..];
+            word_start = true;
+        } else {
+            result.push(c);
+            rest = &rest[c.len_utf8()..];
+            word_start = !c.is_alphanumeric();
+            if c.is_whitespace() {
+                word = text.len() - rest.len();
+            }
+        }
+    }
+    result
+}
+
+// Returns true if the word so far is a drive (e.g., C:) or has a path
+// separator, i.e., if a backslash following it is probably a separator
+fn is_path(word: &str) -> bool {
+    let mut chars = word.chars();
+    matches!((chars.next(), chars.next(), chars.next()),
+             (Some(drive), Some(':'), None) if drive.is_ascii_alphabetic())
+        || word.contains(|c| c == '\\' || c == '/')
+}
+
+// Returns the code point if the whole word is a single escaped character,
+// e.g., U+2192, \u{2192}, &#x2192;, &#8594;, &rarr;, or %E2%86%92
+pub fn parse_code_point(word: &str) -> Option<u32> {
+    // A CSS escape that is the whole word needs no terminator
+    if let Some(hex) = word.strip_prefix('\\') {
+        let size = hex_len(hex, 6);
+        if size >= 2 && size == hex.len() {
+            return char_for(hex).map(|c| c as u32);
+        }
+    }
+    match scan_escape(word, true, false) {
+        Some((unescaped, size)) if size == word.len() => {
+            let mut chars = unescaped.chars();
+            match (chars.next(), chars.next()) {
+                (Some(c), None) => Some(c as u32),
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
+
+// Returns where the hex digits (with any U+ prefix) at the end of the text
+// start and their value; at most six digits are used and only as many as
+// give a value no greater than U+10FFFF
+pub fn code_point_at_end(text: &str) -> Option<(usize, u32)> {
+    let digits = text
+        .chars()
+        .rev()
+        .take_while(|c| c.is_ascii_hexdigit())
+        .take(6)
+        .count();
+    for size in (1..=digits).rev() {
+        let start = text.len() - size; // The digits are ASCII
+        if let Ok(cp) = u32::from_str_radix(&text[start..], 16) {
+            if cp <= 0x10FFFF {
+                let prefix = &text[..start];
+                if prefix.ends_with("U+") || prefix.ends_with("u+") {
+                    return Some((start - 2, cp));
+                }
+                return Some((start, cp));
+            }
+        }
+    }
+    None
+}
+
+// Noncharacters are U+FDD0..U+FDEF and the last two code points of every
+// plane
+pub fn is_noncharacter(cp: u32) -> bool {
+    (0xFDD0..=0xFDEF).contains(&cp) || cp & 0xFFFE == 0xFFFE
+}
+
+// Returns the text with every backslash, control, invisible, and
+// whitespace character (apart from inner spaces) and any leading quote
+// written as \u{H…} so that it survives being saved in the configuration
+// file (which would otherwise treat a quoted value as spanning lines)
+pub fn encode_snippet(text: &str) -> String {
+    let last = text.chars().count().saturating_sub(1);
+    let mut result = String::with_capacity(text.len());
+    for (i, c) in text.chars().enumerate() {
+        if c == '\\'
+            || c.is_control()
+            || (c.is_whitespace() && (c != ' ' || i == 0 || i == last))
+            || (i == 0 && (c == '"' || c == '\''))
+            || is_invisible(c)
+        {
+            result.push_str(&format!("\\u{{{:X}}}", c as u32));
+        } else {
+            result.push(c);
+        }
+    }
+    result
+}
+
+// The inverse of encode_snippet(); only \u{H…} escapes are recognized
+pub fn decode_snippet(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(c) = rest.chars().next() {
+        if let Some(braced) = rest.strip_prefix("\\u{") {
+            let size = hex_len(braced, 6);
+            if size > 0 && braced[size..].starts_with('}') {
+                if let Some(c) = char_for(&braced[..size]) {
+                    result.push(c);
+                    rest = &braced[size + 1..];
+                    continue;
+                }
+            }
+        }
+        result.push(c);
+        rest = &rest[c.len_utf8()..];
+    }
+    result
+}
+
+// Bidi controls and characters that have no glyph of their own (e.g.,
+// zero width joiners and variation selectors)
+pub fn is_invisible(c: char) -> bool {
+    is_bidi_control(c)
+        || matches!(c,
+            '\u{00AD}'
+            | '\u{034F}'
+            | '\u{115F}'
+            | '\u{1160}'
+            | '\u{17B4}'
+            | '\u{17B5}'
+            | '\u{180B}'..='\u{180F}'
+            | '\u{200B}'..='\u{200D}'
+            | '\u{2060}'..='\u{2064}'
+            | '\u{206A}'..='\u{206F}'
+            | '\u{3164}'
+            | '\u{FE00}'..='\u{FE0F}'
+            | '\u{FEFF}'
+            | '\u{FFA0}'
+            | '\u{E0000}'..='\u{E0FFF}')
+}
+
+pub fn is_bidi_control(c: char) -> bool {
+    matches!(c,
+        '\u{061C}'
+        | '\u{200E}'
+        | '\u{200F}'
+        | '\u{202A}'..='\u{202E}'
+        | '\u{2066}'..='\u{2069}')
+}
+
+// If the text starts with an escape returns what it represents and the
+// escape's length in bytes; U+HHHH is only recognized at the start of a
+// word so that, e.g., menu+cafe isn't taken as men and U+CAFE, and CSS
+// escapes aren't recognized in paths so that, e.g., C:\dev\cafe is kept
+fn scan_escape(
+    text: &str,
+    word_start: bool,
+    in_path: bool,
+) -> Option<(String, usize)> {
+    let bytes = text.as_bytes();
+    match bytes.first()? {
+        b'\\' => scan_backslash(text, !in_path),
+        b'&' => scan_entity(text),
+        b'%' => scan_percent(text),
+        b'U' | b'u' if word_start && bytes.get(1) == Some(&b'+') => {
+            let size = hex_len(&text[2..], 6);
+            if size < 4 {
+                return None;
+            }
+            Some((char_for(&text[2..2 + size])?.to_string(), 2 + size))
+        }
+        _ => None,
+    }
+}
+
+// \u{H…} (Rust, JavaScript), \uHHHH (with surrogate pairs), \UHHHHHHHH,
+// \xHH, or (if css is true) CSS's \H… if a space (which is part of it) or
+// punctuation terminates it so that words like \bad are left alone
+fn scan_backslash(text: &str, css: bool) -> Option<(String, usize)> {
+    let rest = &text[1..];
+    if let Some(braced) =
+        rest.strip_prefix("u{").or_else(|| rest.strip_prefix("U{"))
+    {
+        let size = hex_len(braced, 6);
+        if size > 0 && braced[size..].starts_with('}') {
+            return Some((
+                char_for(&braced[..size])?.to_string(),
+                size + 4,
+            ));
+        }
+        return None;
+    }
+    if let Some(hex) = rest.strip_prefix('u') {
+        if hex_len(hex, 4) != 4 {
+            return None;
+        }
+        let high = u32::from_str_radix(&hex[..4], 16).ok()?;
+        if (0xD800..=0xDBFF).contains(&high) {
+            let low = hex[4..].strip_prefix("\\u")?;
+            if hex_len(low, 4) != 4 {
+                return None;
+            }
+            let low = u32::from_str_radix(&low[..4], 16).ok()?;
+            if !(0xDC00..=0xDFFF).contains(&low) {
+                return None;
+            }
+            let cp = 0x10000 + ((high - 0xD800) << 10) + (low - 0xDC00);
+            return Some((char::from_u32(cp)?.to_string(), 12));
+        }
+        return Some((char::from_u32(high)?.to_string(), 6));
+    }
+    if let Some(hex) = rest.strip_prefix('U') {
+        if hex_len(hex, 8) != 8 {
+            return None;
+        }
+        return Some((char_for(&hex[..8])?.to_string(), 10));
+    }
+    if let Some(hex) = rest.strip_prefix('x') {
+        if hex_len(hex, 2) != 2 {
+            return None;
+        }
+        return Some((char_for(&hex[..2])?.to_string(), 4));
+    }
+    // Single hex digit escapes are too easily confused with paths
+    let size = hex_len(rest, 6);
+    if !css || size < 2 {
+        return None;
+    }
+    let space = match rest[size..].chars().next() {
+        Some(' ') => 1,
+        Some(c) if !c.is_alphanumeric() => 0,
+        _ => return None,
+    };
+    let c = char_for(&rest[..size])?;
+    Some((c.to_string(), 1 + size + space))
+}
+
+// &name; &#DDDD; or &#xHHHH;
+fn scan_entity(text: &str) -> Option<(String, usize)> {
+    let end = text.find(';')?;
+    let body = &text[1..end];
+    let c = if let Some(number) = body.strip_prefix('#') {
+        if let Some(hex) =
+            number.strip_prefix('x').or_else(|| number.strip_prefix('X'))
+        {
+            char_for(hex)?
+        } else {
+            char::from_u32(number.parse::<u32>().ok()?)?
+        }
+    } else if body == "apos" {
+        '\''
+    } else {
+        char_for_entity(body)?
+    };
+    Some((c.to_string(), end + 1))
+}
+
+// One or more %HH; as many of them as are valid UTF-8 are used (the run
+// ends at anything else, e.g., a lone % or a multibyte character)
+fn scan_percent(text: &str) -> Option<(String, usize)> {
+    let bytes = text.as_bytes();
+    let mut decoded = vec![];
+    let mut size = 0;
+    while bytes.get(size) == Some(&b'%') {
+        let hex = match text.get(size + 1..size + 3) {
+            Some(hex) if hex_len(hex, 2) == 2 => hex,
+            _ => break,
+        };
+        match u8::from_str_radix(hex, 16) {
+            Ok(byte) => decoded.push(byte),
+            Err(_) => break,
+        }
+        size += 3;
+    }
+    let valid = match std::str::from_utf8(&decoded) {
+        Ok(valid) => valid,
+        Err(err) => std::str::from_utf8(&decoded[..err.valid_up_to()])
+            .unwrap_or_default(),
+    };
+    if valid.is_empty() {
+        return None;
+    }
+    Some((valid.to_string(), valid.len() * 3))
+}
+
+// Returns how many of the leading bytes (up to maximum) are hex digits
+fn hex_len(text: &str, maximum: usize) -> usize {
+    text.bytes().take(maximum).take_while(|b| b.is_ascii_hexdigit()).count()
+}
+
+fn char_for(hex: &str) -> Option<char> {
+    char::from_u32(u32::from_str_radix(hex, 16).ok()?)
+}
+
+pub fn entity_for(c: char) -> Option<&'static str> {
+    ENTITIES.iter().find(|(_, e)| *e == c).map(|(name, _)| *name)
+}
+
+pub fn char_for_entity(name: &str) -> Option<char> {
+    ENTITIES.iter().find(|(n, _)| *n == name).map(|(_, c)| *c)
+}
+
+fn latex_for(c: char) -> Option<&'static str> {
+    LATEX.iter().find(|(l, _)| *l == c).map(|(_, command)| *command)
+}
+
+// The HTML 4 named character entities
+static ENTITIES: [(&str, char); 252] = [
+    ("quot", '"'),
+    ("amp", '&'),
+    ("lt", '<'),
+    ("gt", '>'),
+    ("nbsp", '\u{A0}'),
+    ("iexcl", '¡'),
+    ("cent", '¢'),
+    ("pound", '£'),
+    ("curren", '¤'),
+    ("yen", '¥'),
+    ("brvbar", '¦'),
+    ("sect", '§'),
+    ("uml", '¨'),
+    ("copy", '©'),
+    ("ordf", 'ª'),
+    ("laquo", '«'),
+    ("not", '¬'),
+    ("shy", '\u{AD}'),
+    ("reg", '®'),
+    ("macr", '¯'),
+    ("deg", '°'),
+    ("plusmn", '±'),
+    ("sup2", '²'),
+    ("sup3", '³'),
+    ("acute", '´'),
+    ("micro", 'µ'),
+    ("para", '¶'),
+    ("middot", '·'),
+    ("cedil", '¸'),
+    ("sup1", '¹'),
+    ("ordm", 'º'),
+    ("raquo", '»'),
+    ("frac14", '¼'),
+    ("frac12", '½'),
+    ("frac34", '¾'),
+    ("iquest", '¿'),
+    ("Agrave", 'À'),
+    ("Aacute", 'Á'),
+    ("Acirc", 'Â'),
+    ("Atilde", 'Ã'),
+    ("Auml", 'Ä'),
+    ("Aring", 'Å'),
+    ("AElig", 'Æ'),
+    ("Ccedil", 'Ç'),
+    ("Egrave", 'È'),
+    ("Eacute", 'É'),
+    ("Ecirc", 'Ê'),
+    ("Euml", 'Ë'),
+    ("Igrave", 'Ì'),
+    ("Iacute", 'Í'),
+    ("Icirc", 'Î'),
+    ("Iuml", 'Ï'),
+    ("ETH", 'Ð'),
+    ("Ntilde", 'Ñ'),
+    ("Ograve", 'Ò'),
+    ("Oacute", 'Ó'),
+    ("Ocirc", 'Ô'),
+    ("Otilde", 'Õ'),
+    ("Ouml", 'Ö'),
+    ("times", '×'),
+    ("Oslash", 'Ø'),
+    ("Ugrave", 'Ù'),
+    ("Uacute", 'Ú'),
+    ("Ucirc", 'Û'),
+    ("Uuml", 'Ü'),
+    ("Yacute", 'Ý'),
+    ("THORN", 'Þ'),
+    ("szlig", 'ß'),
+    ("agrave", 'à'),
+    ("aacute", 'á'),
+    ("acirc", 'â'),
+    ("atilde", 'ã'),
+    ("auml", 'ä'),
+    ("aring", 'å'),
+    ("aelig", 'æ'),
+    ("ccedil", 'ç'),
+    ("egrave", 'è'),
+    ("eacute", 'é'),
+    ("ecirc", 'ê'),
+    ("euml", 'ë'),
+    ("igrave", 'ì'),
+    ("iacute", 'í'),
+    ("icirc", 'î'),
+    ("iuml", 'ï'),
+    ("eth", 'ð'),
+    ("ntilde", 'ñ'),
+    ("ograve", 'ò'),
+    ("oacute", 'ó'),
+    ("ocirc", 'ô'),
+    ("otilde", 'õ'),
+    ("ouml", 'ö'),
+    ("divide", '÷'),
+    ("oslash", 'ø'),
+    ("ugrave", 'ù'),
+    ("uacute", 'ú'),
+    ("ucirc", 'û'),
+    ("uuml", 'ü'),
+    ("yacute", 'ý'),
+    ("thorn", 'þ'),
+    ("yuml", 'ÿ'),
+    ("OElig", 'Œ'),
+    ("oelig", 'œ'),
+    ("Scaron", 'Š'),
+    ("scaron", 'š'),
+    ("Yuml", 'Ÿ'),
+    ("fnof", 'ƒ'),
+    ("circ", 'ˆ'),
+    ("tilde", '˜'),
+    ("Alpha", 'Α'),
+    ("Beta", 'Β'),
+    ("Gamma", 'Γ'),
+    ("Delta", 'Δ'),
+    ("Epsilon", 'Ε'),
+    ("Zeta", 'Ζ'),
+    ("Eta", 'Η'),
+    ("Theta", 'Θ'),
+    ("Iota", 'Ι'),
+    ("Kappa", 'Κ'),
+    ("Lambda", 'Λ'),
+    ("Mu", 'Μ'),
+    ("Nu", 'Ν'),
+    ("Xi", 'Ξ'),
+    ("Omicron", 'Ο'),
+    ("Pi", 'Π'),
+    ("Rho", 'Ρ'),
+    ("Sigma", 'Σ'),
+    ("Tau", 'Τ'),
+    ("Upsilon", 'Υ'),
+    ("Phi", 'Φ'),
+    ("Chi", 'Χ'),
+    ("Psi", 'Ψ'),
+    ("Omega", 'Ω'),
+    ("alpha", 'α'),
+    ("beta", 'β'),
+    ("gamma", 'γ'),
+    ("delta", 'δ'),
+    ("epsilon", 'ε'),
+    ("zeta", 'ζ'),
+    ("eta", 'η'),
+    ("theta", 'θ'),
+    ("iota", 'ι'),
+    ("kappa", 'κ'),
+    ("lambda", 'λ'),
+    ("mu", 'μ'),
+    ("nu", 'ν'),
+    ("xi", 'ξ'),
+    ("omicron", 'ο'),
+    ("pi", 'π'),
+    ("rho", 'ρ'),
+    ("sigmaf", 'ς'),
+    ("sigma", 'σ'),
+    ("tau", 'τ'),
+    ("upsilon", 'υ'),
+    ("phi", 'φ'),
+    ("chi", 'χ'),
+    ("psi", 'ψ'),
+    ("omega", 'ω'),
+    ("thetasym", 'ϑ'),
+    ("upsih", 'ϒ'),
+    ("piv", 'ϖ'),
+    ("ensp", '\u{2002}'),
+    ("emsp", '\u{2003}'),
+    ("thinsp", '\u{2009}'),
+    ("zwnj", '\u{200C}'),
+    ("zwj", '\u{200D}'),
+    ("lrm", '\u{200E}'),
+    ("rlm", '\u{200F}'),
+    ("ndash", '–'),
+    ("mdash", '—'),
+    ("lsquo", '‘'),
+    ("rsquo", '’'),
+    ("sbquo", '‚'),
+    ("ldquo", '“'),
+    ("rdquo", '”'),
+    ("bdquo", '„'),
+    ("dagger", '†'),
+    ("Dagger", '‡'),
+    ("bull", '•'),
+    ("hellip", '…'),
+    ("permil", '‰'),
+    ("prime", '′'),
+    ("Prime", '″'),
+    ("lsaquo", '‹'),
+    ("rsaquo", '›'),
+    ("oline", '‾'),
+    ("frasl", '⁄'),
+    ("euro", '€'),
+    ("image", 'ℑ'),
+    ("weierp", '℘'),
+    ("real", 'ℜ'),
+    ("trade", '™'),
+    ("alefsym", 'ℵ'),
+    ("larr", '←'),
+    ("uarr", '↑'),
+    ("rarr", '→'),
+    ("darr", '↓'),
+    ("harr", '↔'),
+    ("crarr", '↵'),
+    ("lArr", '⇐'),
+    ("uArr", '⇑'),
+    ("rArr", '⇒'),
+    ("dArr", '⇓'),
+    ("hArr", '⇔'),
+    ("forall", '∀'),
+    ("part", '∂'),
+    ("exist", '∃'),
+    ("empty", '∅'),
+    ("nabla", '∇'),
+    ("isin", '∈'),
+    ("notin", '∉'),
+    ("ni", '∋'),
+    ("prod", '∏'),
+    ("sum", '∑'),
+    ("minus", '−'),
+    ("lowast", '∗'),
+    ("radic", '√'),
+    ("prop", '∝'),
+    ("infin", '∞'),
+    ("ang", '∠'),
+    ("and", '∧'),
+    ("or", '∨'),
+    ("cap", '∩'),
+    ("cup", '∪'),
+    ("int", '∫'),
+    ("there4", '∴'),
+    ("sim", '∼'),
+    ("cong", '≅'),
+    ("asymp", '≈'),
+    ("ne", '≠'),
+    ("equiv", '≡'),
+    ("le", '≤'),
+    ("ge", '≥'),
+    ("sub", '⊂'),
+    ("sup", '⊃'),
+    ("nsub", '⊄'),
+    ("sube", '⊆'),
+    ("supe", '⊇'),
+    ("oplus", '⊕'),
+    ("otimes", '⊗'),
+    ("perp", '⊥'),
+    ("sdot", '⋅'),
+    ("lceil", '⌈'),
+    ("rceil", '⌉'),
+    ("lfloor", '⌊'),
+    ("rfloor", '⌋'),
+    ("lang", '〈'),
+    ("rang", '〉'),
+    ("loz", '◊'),
+    ("spades", '♠'),
+    ("clubs", '♣'),
+    ("hearts", '♥'),
+    ("diams", '♦'),
+];
+
+static LATEX: [(char, &str); 66] = [
+    ('#', "\\#"),
+    ('$', "\\$"),
+    ('%', "\\%"),
+    ('&', "\\&"),
+    ('\\', "\\textbackslash{}"),
+    ('^', "\\textasciicircum{}"),
+    ('_', "\\_"),
+    ('{', "\\{"),
+    ('}', "\\}"),
+    ('~', "\\textasciitilde{}"),
+    ('\u{A0}', "~"),
+    ('§', "\\S{}"),
+    ('©', "\\textcopyright{}"),
+    ('®', "\\textregistered{}"),
+    ('°', "\\textdegree{}"),
+    ('±', "$\\pm$"),
+    ('¶', "\\P{}"),
+    ('·', "\\textperiodcentered{}"),
+    ('×', "$\\times$"),
+    ('÷', "$\\div$"),
+    ('–', "--"),
+    ('—', "---"),
+    ('‘', "`"),
+    ('’', "'"),
+    ('“', "``"),
+    ('”', "''"),
+    ('†', "\\dag{}"),
+    ('‡', "\\ddag{}"),
+    ('•', "\\textbullet{}"),
+    ('…', "\\ldots{}"),
+    ('€', "\\euro{}"),
+    ('£', "\\pounds{}"),
+    ('™', "\\texttrademark{}"),
+    ('α', "$\\alpha$"),
+    ('β', "$\\beta$"),
+    ('γ', "$\\gamma$"),
+    ('δ', "$\\delta$"),
+    ('ε', "$\\epsilon$"),
+    ('θ', "$\\theta$"),
+    ('λ', "$\\lambda$"),
+    ('μ', "$\\mu$"),
+    ('π', "$\\pi$"),
+    ('σ', "$\\sigma$"),
+    ('φ', "$\\phi$"),
+    ('ω', "$\\omega$"),
+    ('Δ', "$\\Delta$"),
+    ('Σ', "$\\Sigma$"),
+    ('Ω', "$\\Omega$"),
+    ('←', "$\\leftarrow$"),
+    ('↑', "$\\uparrow$"),
+    ('→', "$\\rightarrow$"),
+    ('↓', "$\\downarrow$"),
+    ('↔', "$\\leftrightarrow$"),
+    ('⇐', "$\\Leftarrow$"),
+    ('⇒', "$\\Rightarrow$"),
+    ('⇔', "$\\Leftrightarrow$"),
+    ('∀', "$\\forall$"),
+    ('∃', "$\\exists$"),
+    ('∈', "$\\in$"),
+    ('∑', "$\\sum$"),
+    ('√', "$\\surd$"),
+    ('∞', "$\\infty$"),
+    ('≈', "$\\approx$"),
+    ('≠', "$\\neq$"),
+    ('≤', "$\\leq$"),
+    ('≥', "$\\geq$"),
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unescape_recognizes_each_form() {
+        assert_eq!(unescape("\\u{2192} \\u00E9 \\U0001F600"), "→ é 😀");
+        assert_eq!(unescape("\\uD83D\\uDE00"), "😀");
+        assert_eq!(unescape("\\xE9t\\xE9"), "été");
+        assert_eq!(unescape("&eacute;&#233;&#xE9;&apos;&rarr;"), "ééé'→");
+        assert_eq!(unescape("%C3%A9t%C3%A9"), "été");
+        assert_eq!(unescape("U+00E9 and u+2192"), "é and →");
+        assert_eq!(unescape("caf\\E9  ok"), "café ok");
+        assert_eq!(unescape("caf\\E9, \"\\2192\""), "café, \"→\"");
+        assert_eq!(unescape("\\E9 \\2192 "), "é→");
+    }
+
+    #[test]
+    fn unescape_leaves_other_text_alone() {
+        for text in [
+            "C:\\dev\\bin",
+            "C:\\dev\\cafe",
+            "C:\\dev\\cafe\\",
+            "C:\\Users\\me\\face photo.png",
+            "\\dev",
+            "\\bad",
+            "caf\\E9",
+            "a\\b",
+            "menu+cafe",
+            "100%",
+            "%C3",
+            "%ZZ",
+            "&unknown;",
+            "U+12",
+            "\\u12",
+        ] {
+            assert_eq!(unescape(text), text);
+        }
+    }
+
+    #[test]
+    fn unescape_keeps_a_partial_percent_run() {
+        assert_eq!(unescape("%C3%A9%"), "é%");
+        assert_eq!(unescape("%C3%A9%é"), "é%é");
+        assert_eq!(unescape("%C3%A9%C3"), "é%C3");
+    }
+
+    #[test]
+    fn unescape_reverses_encode() {
+        let text = "café → ok 1";
+        for format in [
+            Format::Text,
+            Format::Rust,
+            Format::HtmlName,
+            Format::HtmlHex,
+            Format::Css,
+            Format::Url,
+        ] {
+            assert_eq!(unescape(&format.encode(text)), text, "{format:?}");
+        }
+    }
+
+    #[test]
+    fn parse_code_point_needs_a_whole_escape() {
+        assert_eq!(parse_code_point("U+2192"), Some(0x2192));
+        assert_eq!(parse_code_point("&rarr;"), Some(0x2192));
+        assert_eq!(parse_code_point("%E2%86%92"), Some(0x2192));
+        assert_eq!(parse_code_point("\\2192"), Some(0x2192));
+        assert_eq!(parse_code_point("U+2192x"), None);
+        assert_eq!(parse_code_point("arrow"), None);
+    }
+}
@@ -0,0 +1,546 @@
+// Copyright © 2021-22 Mark Summerfield. All rights reserved.
+// License: GPLv3
+
+use crate::config::Config;
+use crate::escape::Format;
+use crate::fixed::{
+    APPNAME, AUTO_MENU_SIZE, COMPARE_SIZE_MAX, COMPARE_SIZE_MIN,
+    GLYPH_SIZE_MAX, GLYPH_SIZE_MIN,
+};
+use crate::history::{History, HistoryItem, HistoryOrder};
+use crate::theme::Theme;
+use crate::util;
+use serde_json::{json, Map, Value};
+use std::{fs, path::Path};
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Category {
+    Searches,
+    History,
+    Favorites,
+    Snippets,
+    Settings,
+}
+
+impl Category {
+    pub const ALL: [Category; 5] = [
+        Category::Searches,
+        Category::History,
+        Category::Favorites,
+        Category::Snippets,
+        Category::Settings,
+    ];
+
+    // The key used in the exported file
+    pub fn name(&self) -> &'static str {
+        match self {
+            Category::Searches => "searches",
+            Category::History => "history",
+            Category::Favorites => "favorites",
+            Category::Snippets => "snippets",
+            Category::Settings => "settings",
+        }
+    }
+
+    // The name shown to the user
+    pub fn label(&self) -> &'static str {
+        match self {
+            Category::Searches => "&Searches",
+            Category::History => "&History",
+            Category::Favorites => "&Favorites",
+            Category::Snippets => "S&nippets",
+            Category::Settings => "Se&ttings",
+        }
+    }
+}
+
+// Only the chosen categories are exported; the window's position, size,
+// and scale never are since they depend on the screen
+pub fn export(
+    config: &Config,
+    categories: &[Category],
+    filename: &Path,
+) -> Result<(), String> {
+    let mut data = Map::new();
+    data.insert(APP_KEY.to_string(), json!(APPNAME));
+    data.insert(VERSION_KEY.to_string(), json!(EXCHANGE_VERSION));
+    for category in categories.iter() {
+        let value = match category {
+            Category::Searches => export_searches(config),
+            Category::History => export_history(&config.history),
+            Category::Favorites => export_favorites(config),
+            Category::Snippets => export_snippets(config),
+            Category::Settings => export_settings(config),
+        };
+        data.insert(category.name().to_string(), value);
+    }
+    let text = serde_json::to_string_pretty(&Value::Object(data))
+        .map_err(|err| format!("failed to export: {err}"))?;
+    fs::write(filename, text + "\n")
+        .map_err(|err| format!("failed to export: {err}"))
+}
+
+fn export_searches(config: &Config) -> Value {
+    Value::Array(
+        config
+            .searches
+            .iter()
+            .enumerate()
+            .map(|(i, search)| {
+                json!({
+                    TEXT_KEY: search,
+                    PINNED_KEY: i < config.searches_pinned,
+                })
+            })
+            .collect(),
+    )
+}
+
+fn export_history(history: &History) -> Value {
+    Value::Array(
+        history
+            .items()
+            .iter()
+            .map(|item| {
+                json!({
+                    CHAR_KEY: item.c.to_string(),
+                    COUNT_KEY: item.count,
+                    LAST_USED_KEY: item.last_used,
+                    PINNED_KEY: item.pinned,
+                })
+            })
+            .collect(),
+    )
+}
+
+fn export_favorites(config: &Config) -> Value {
+    Value::Array(
+        config
+            .favorites
+            .iter()
+            .map(|(name, chars)| {
+                json!({
+                    NAME_KEY: name,
+                    CHARS_KEY: chars.iter().collect::<String>(),
+                })
+            })
+            .collect(),
+    )
+}
+
+fn export_snippets(config: &Config) -> Value {
+    Value::Array(
+        config
+            .snippets
+            .iter()
+            .map(|(name, text)| json!({NAME_KEY: name, TEXT_KEY: text}))
+            .collect(),
+    )
+}
+
+fn export_settings(config: &Config) -> Value {
+    json!({
+        THEME_KEY: config.theme.name(),
+        SEARCH_SIZE_KEY: config.searches_size,
+        HISTORY_SIZE_KEY: config.history_size,
+        HISTORY_ORDER_KEY: config.history_order.name(),
+        COPY_FORMAT_KEY: config.copy_format.name(),
+        COPY_AS_DEFAULT_KEY: config.copy_as_default,
+        COMPLETION_PREFIX_KEY: config.completion_prefix,
+        COMPARE_SIZE_KEY: config.compare_size,
+        GLYPH_SIZE_KEY: config.glyph_size,
+    })
+}
+
+// A file read for importing; entries that aren't valid are skipped
+pub struct Import {
+    data: Map<String, Value>,
+}
+
+impl Import {
+    pub fn load(filename: &Path) -> Result<Self, String> {
+        let text = fs::read_to_string(filename)
+            .map_err(|err| format!("failed to import: {err}"))?;
+        match serde_json::from_str(&text) {
+            Ok(Value::Object(data))
+                if data.get(APP_KEY).and_then(Value::as_str)
+                    == Some(APPNAME) =>
+            {
+                Ok(Self { data })
+            }
+            Ok(_) => Err(format!(
+                "failed to import: this isn't an exported {APPNAME} file"
+            )),
+            Err(err) => Err(format!("failed to import: {err}")),
+        }
+    }
+
+    // The categories the file holds
+    pub fn categories(&self) -> Vec<Category> {
+        Category::ALL
+            .iter()
+            .filter(|category| self.data.contains_key(category.name()))
+            .copied()
+            .collect()
+    }
+
+    // Merging keeps what is already there and adds or updates from the
+    // file; replacing discards what is there
+    pub fn apply(
+        &self,
+        config: &mut Config,
+        categories: &[Category],
+        merge: bool,
+    ) {
+        for category in categories.iter() {
+            if let Some(value) = self.data.get(category.name()) {
+                match category {
+                    Category::Searches => {
+                        import_searches(config, value, merge)
+                    }
+                    Category::History => {
+                        import_history(config, value, merge)
+                    }
+                    Category::Favorites => {
+                        import_favorites(config, value, merge)
+                    }
+                    Category::Snippets => {
+                        import_snippets(config, value, merge)
+                    }
+                    Category::Settings => import_settings(config, value),
+                }
+            }
+        }
+    }
+}
+
+fn entries(value: &Value) -> &[Value] {
+    match value.as_array() {
+        Some(entries) => entries,
+        None => &[],
+    }
+}
+
+fn text<'a>(entry: &'a Value, key: &str) -> Option<&'a str> {
+    entry.get(key).and_then(Value::as_str)
+}
+
+fn pinned(entry: &Value) -> bool {
+    entry.get(PINNED_KEY).and_then(Value::as_bool).unwrap_or_default()
+}
+
+// Merged searches are deduplicated just like new searches
+fn import_searches(config: &mut Config, value: &Value, merge: bool) {
+    let searches = entries(value)
+        .iter()
+        .filter_map(|entry| {
+            let search = text(entry, TEXT_KEY)?.trim();
+            if search.is_empty() {
+                None
+            } else {
+                Some((search.to_string(), pinned(entry)))
+            }
+        })
+        .collect::<Vec<(String, bool)>>();
+    if !merge {
+        config.searches.clear();
+        config.searches_pinned = 0;
+    }
+    let mut pinned = config.searches_pinned.min(config.searches.len());
+    for (search, _) in searches.iter().filter(|(_, pinned)| *pinned) {
+        if let Some(i) = config.searches.iter().position(|s| s == search) {
+            if i < pinned {
+                continue;
+            }
+            config.searches.remove(i);
+        }
+        config.searches.insert(pinned, search.clone());
+        pinned += 1;
+    }
+    let unpinned = searches.iter().filter(|(_, pinned)| !*pinned);
+    if merge {
+        // Last first so that the first ends up first
+        for (search, _) in unpinned.rev() {
+            util::merge_search(&mut config.searches, pinned, search);
+        }
+    } else {
+        for (search, _) in unpinned {
+            if !config.searches.contains(search) {
+                config.searches.push_back(search.clone());
+            }
+        }
+    }
+    config.searches.truncate(config.searches_size.max(pinned));
+    config.searches_pinned = pinned.min(config.searches.len());
+}
+
+fn import_history(config: &mut Config, value: &Value, merge: bool) {
+    let mut history = History::default();
+    for entry in entries(value).iter() {
+        if let Some(c) =
+            text(entry, CHAR_KEY).and_then(|s| s.chars().next())
+        {
+            history.push(HistoryItem {
+                c,
+                count: entry
+                    .get(COUNT_KEY)
+                    .and_then(Value::as_u64)
+                    .map(|count| count.min(u32::MAX as u64) as u32)
+                    .unwrap_or_default(),
+                last_used: entry
+                    .get(LAST_USED_KEY)
+                    .and_then(Value::as_i64)
+                    .unwrap_or_default(),
+                pinned: pinned(entry),
+            });
+        }
+    }
+    if merge {
+        config.history.merge(&history);
+    } else {
+        config.history = history;
+    }
+}
+
+// A group with the same name as an existing one gains its new characters
+fn import_favorites(config: &mut Config, value: &Value, merge: bool) {
+    if !merge {
+        config.favorites.clear();
+    }
+    for entry in entries(value).iter() {
+        let name =
+            util::menu_name(text(entry, NAME_KEY).unwrap_or_default());
+        if name.is_empty() {
+            continue;
+        }
+        let chars = text(entry, CHARS_KEY)
+            .unwrap_or_default()
+            .chars()
+            .filter(|c| !c.is_whitespace());
+        match config.favorites.iter_mut().find(|(n, _)| *n == name) {
+            Some((_, old)) => {
+                for c in chars {
+                    if !old.contains(&c) {
+                        old.push(c);
+                    }
+                }
+            }
+            None => {
+                let mut new = vec![];
+                for c in chars {
+                    if !new.contains(&c) {
+                        new.push(c);
+                    }
+                }
+                config.favorites.push((name, new));
+            }
+        }
+    }
+}
+
+// A snippet with the same name as an existing one replaces its text
+fn import_snippets(config: &mut Config, value: &Value, merge: bool) {
+    if !merge {
+        config.snippets.clear();
+    }
+    for entry in entries(value).iter() {
+        let name =
+            util::menu_name(text(entry, NAME_KEY).unwrap_or_default());
+        let snippet = text(entry, TEXT_KEY).unwrap_or_default();
+        if name.is_empty() || snippet.is_empty() {
+            continue;
+        }
+        match config.snippets.iter_mut().find(|(n, _)| *n == name) {
+            Some((_, old)) => *old = snippet.to_string(),
+            None => config.snippets.push((name, snippet.to_string())),
+        }
+    }
+}
+
+// Missing or invalid settings are left unchanged
+fn import_settings(config: &mut Config, value: &Value) {
+    if let Some(theme) = text(value, THEME_KEY).and_then(Theme::from_name) {
+        config.theme = theme;
+    }
+    config.searches_size =
+        number(value, SEARCH_SIZE_KEY, 2, AUTO_MENU_SIZE as i64)
+            .map_or(config.searches_size, |size| size as usize);
+    config.history_size =
+        number(value, HISTORY_SIZE_KEY, 2, AUTO_MENU_SIZE as i64)
+            .map_or(config.history_size, |size| size as usize);
+    if let Some(order) =
+        text(value, HISTORY_ORDER_KEY).and_then(HistoryOrder::from_name)
+    {
+        config.history_order = order;
+    }
+    if let Some(format) =
+        text(value, COPY_FORMAT_KEY).and_then(Format::from_name)
+    {
+        config.copy_format = format;
+    }
+    if let Some(copy_as_default) =
+        value.get(COPY_AS_DEFAULT_KEY).and_then(Value::as_bool)
+    {
+        config.copy_as_default = copy_as_default;
+    }
+    if let Some(prefix) = text(value, COMPLETION_PREFIX_KEY) {
+        // Letters and digits would be taken as part of the token
+        config.completion_prefix = prefix
+            .chars()
+            .filter(|c| !c.is_alphanumeric() && !c.is_whitespace())
+            .collect();
+    }
+    config.compare_size = number(
+        value,
+        COMPARE_SIZE_KEY,
+        COMPARE_SIZE_MIN as i64,
+        COMPARE_SIZE_MAX as i64,
+    )
+    .map_or(config.compare_size, |size| size as i32);
+    config.glyph_size = number(
+        value,
+        GLYPH_SIZE_KEY,
+        GLYPH_SIZE_MIN as i64,
+        GLYPH_SIZE_MAX as i64,
+    )
+    .map_or(config.glyph_size, |size| size as i32);
+}
+
+fn number(
+    value: &Value,
+    key: &str,
+    minimum: i64,
+    maximum: i64,
+) -> Option<i64> {
+    value
+        .get(key)
+        .and_then(Value::as_i64)
+        .filter(|n| (minimum..=maximum).contains(n))
+}
+
+const EXCHANGE_VERSION: u32 = 1;
+
+static APP_KEY: &str = "app";
+static VERSION_KEY: &str = "version";
+static TEXT_KEY: &str = "text";
+static PINNED_KEY: &str = "pinned";
+static CHAR_KEY: &str = "char";
+static COUNT_KEY: &str = "count";
+static LAST_USED_KEY: &str = "last-used";
+static NAME_KEY: &str = "name";
+static CHARS_KEY: &str = "chars";
+static THEME_KEY: &str = "theme";
+static SEARCH_SIZE_KEY: &str = "search-size";
+static HISTORY_SIZE_KEY: &str = "history-size";
+static HISTORY_ORDER_KEY: &str = "history-order";
+static COPY_FORMAT_KEY: &str = "copy-format";
+static COPY_AS_DEFAULT_KEY: &str = "copy-as-default";
+static COMPLETION_PREFIX_KEY: &str = "completion-prefix";
+static COMPARE_SIZE_KEY: &str = "compare-size";
+static GLYPH_SIZE_KEY: &str = "glyph-size";
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn temp_filename(name: &str) -> PathBuf {
+        std::env::temp_dir()
+            .join(format!("charfind-{}-{name}.json", std::process::id()))
+    }
+
+    fn exported_config() -> Config {
+        let mut config = Config::default();
+        config.searches =
+            ["arrow", "accent", "quote"].map(String::from).into();
+        config.searches_pinned = 1;
+        config.history = History::from_config("2192:3:100:1,E9:1:50");
+        config.favorites = vec![("Arrows".to_string(), vec!['→', '←'])];
+        config.snippets = vec![("Sig".to_string(), " — Me\n".to_string())];
+        config.theme = Theme::Dark;
+        config.searches_size = 12;
+        config.history_size = 8;
+        config.history_order = HistoryOrder::Frequency;
+        config.copy_format = Format::Css;
+        config.copy_as_default = true;
+        config.completion_prefix = ";".to_string();
+        config.compare_size = 64;
+        config.glyph_size = 320;
+        config
+    }
+
+    #[test]
+    fn export_then_import_round_trip() {
+        let filename = temp_filename("round-trip");
+        let config = exported_config();
+        export(&config, &Category::ALL, &filename).unwrap();
+        let import = Import::load(&filename);
+        fs::remove_file(&filename).unwrap();
+        let import = import.unwrap();
+        assert_eq!(import.categories(), Category::ALL);
+        let mut copy = Config::default();
+        import.apply(&mut copy, &Category::ALL, false);
+        assert_eq!(copy.searches, config.searches);
+        assert_eq!(copy.searches_pinned, 1);
+        assert_eq!(copy.history.items(), config.history.items());
+        assert_eq!(copy.favorites, config.favorites);
+        assert_eq!(copy.snippets, config.snippets);
+        assert_eq!(copy.theme, Theme::Dark);
+        assert_eq!(copy.searches_size, 12);
+        assert_eq!(copy.history_size, 8);
+        assert_eq!(copy.history_order, HistoryOrder::Frequency);
+        assert_eq!(copy.copy_format, Format::Css);
+        assert!(copy.copy_as_default);
+        assert_eq!(copy.completion_prefix, ";");
+        assert_eq!(copy.compare_size, 64);
+        assert_eq!(copy.glyph_size, 320);
+    }
+
+    #[test]
+    fn export_only_the_chosen_categories() {
+        let filename = temp_filename("chosen");
+        let config = exported_config();
+        let categories = [Category::Favorites, Category::Snippets];
+        export(&config, &categories, &filename).unwrap();
+        let import = Import::load(&filename);
+        fs::remove_file(&filename).unwrap();
+        assert_eq!(import.unwrap().categories(), categories);
+    }
+
+    #[test]
+    fn import_merges_with_what_is_there() {
+        let filename = temp_filename("merge");
+        export(&exported_config(), &Category::ALL, &filename).unwrap();
+        let import = Import::load(&filename);
+        fs::remove_file(&filename).unwrap();
+        let mut config = Config::default();
+        config.searches = ["quote", "other"].map(String::from).into();
+        config.searches_pinned = 0;
+        config.favorites = vec![("Arrows".to_string(), vec!['↑', '→'])];
+        config.snippets = vec![("Sig".to_string(), "old".to_string())];
+        import.unwrap().apply(
+            &mut config,
+            &[Category::Searches, Category::Favorites, Category::Snippets],
+            true,
+        );
+        assert_eq!(config.searches, ["arrow", "accent", "quote", "other"]);
+        assert_eq!(config.searches_pinned, 1);
+        assert_eq!(
+            config.favorites,
+            [("Arrows".to_string(), vec!['↑', '→', '←'])]
+        );
+        assert_eq!(
+            config.snippets,
+            [("Sig".to_string(), " — Me\n".to_string())]
+        );
+    }
+
+    #[test]
+    fn load_rejects_other_files() {
+        let filename = temp_filename("other");
+        fs::write(&filename, "{\"app\": \"Other\"}\n").unwrap();
+        let import = Import::load(&filename);
+        fs::remove_file(&filename).unwrap();
+        assert!(import.is_err());
+    }
+}
@@ -0,0 +1,150 @@
+// Copyright © 2021-22 Mark Summerfield. All rights reserved.
+// License: GPLv3
+
+use crate::exchange::Category;
+use crate::fixed::{APPNAME, BUTTON_HEIGHT, BUTTON_WIDTH, ICON, PAD};
+use fltk::{
+    app,
+    button::{Button, CheckButton, RadioRoundButton},
+    enums::Align,
+    frame::Frame,
+    group::Flex,
+    image::SvgImage,
+    prelude::*,
+    window::Window,
+};
+use std::cell::RefCell;
+use std::rc::Rc;
+
+pub struct Form {
+    form: Window,
+    pub ok: Rc<RefCell<bool>>,
+    checks: Vec<(Category, CheckButton)>,
+    merge_button: Option<RadioRoundButton>,
+}
+
+impl Form {
+    // Only the available categories can be chosen; importing also offers
+    // a choice of merging or replacing
+    pub fn new(title: &str, available: &[Category], import: bool) -> Self {
+        let ok = Rc::from(RefCell::from(false));
+        let mut form = make_form(title, import);
+        let mut vbox = Flex::default().size_of_parent().column();
+        vbox.set_margin(PAD);
+        vbox.set_pad(PAD);
+        Frame::default()
+            .with_label(if import {
+                "Import these from the file:"
+            } else {
+                "Export these to the file:"
+            })
+            .with_align(Align::Inside | Align::Left);
+        let mut checks = vec![];
+        for category in Category::ALL.iter() {
+            let mut check =
+                CheckButton::default().with_label(category.label());
+            if available.contains(category) {
+                check.set_checked(true);
+            } else {
+                check.deactivate();
+            }
+            if *category == Category::Settings {
+                check.set_tooltip("The theme, menu sizes, history order, Copy As format, completion prefix, and font sizes (but not the window's position, size, or scale)");
+            }
+            checks.push((*category, check));
+        }
+        let merge_button =
+            if import { Some(make_merge_row()) } else { None };
+        let (button_row, mut ok_button, mut cancel_button) =
+            make_bottom_row();
+        vbox.set_size(&button_row, BUTTON_HEIGHT);
+        vbox.end();
+        form.end();
+        form.make_modal(true);
+        ok_button.set_callback({
+            let ok = Rc::clone(&ok);
+            let mut form = form.clone();
+            move |_| {
+                *ok.borrow_mut() = true;
+                form.hide();
+            }
+        });
+        cancel_button.set_callback({
+            let mut form = form.clone();
+            move |_| {
+                form.hide();
+            }
+        });
+        form.show();
+        while form.shown() {
+            app::wait();
+        }
+        Self { form, ok, checks, merge_button }
+    }
+
+    pub fn categories(&self) -> Vec<Category> {
+        self.checks
+            .iter()
+            .filter(|(_, check)| check.active() && check.is_checked())
+            .map(|(category, _)| *category)
+            .collect()
+    }
+
+    pub fn merge(&self) -> bool {
+        match &self.merge_button {
+            Some(merge_button) => merge_button.is_toggled(),
+            None => false,
+        }
+    }
+}
+
+impl Drop for Form {
+    fn drop(&mut self) {
+        app::delete_widget(self.form.clone());
+    }
+}
+
+fn make_form(title: &str, import: bool) -> Window {
+    let image = SvgImage::from_data(ICON).unwrap();
+    let mut form = Window::default()
+        .with_size(WIDTH, if import { HEIGHT + ROW_HEIGHT } else { HEIGHT })
+        .with_label(&format!("{title} — {APPNAME}"));
+    if let Some(window) = app::first_window() {
+        form.set_pos(window.x() + 50, window.y() + 100);
+    }
+    form.set_icon(Some(image));
+    form
+}
+
+fn make_merge_row() -> RadioRoundButton {
+    let mut row = Flex::default().row();
+    row.set_pad(PAD);
+    let mut merge_button = RadioRoundButton::default().with_label("&Merge");
+    merge_button.set_toggled(true);
+    merge_button.set_tooltip(
+        "Keep the existing entries and add the file's new ones (and update the ones with the same names)",
+    );
+    let mut replace_button =
+        RadioRoundButton::default().with_label("&Replace");
+    replace_button
+        .set_tooltip("Discard the existing entries and use the file's");
+    row.end();
+    merge_button
+}
+
+fn make_bottom_row() -> (Flex, Button, Button) {
+    let mut row = Flex::default().row();
+    row.set_pad(PAD);
+    Frame::default(); // pad left of buttons
+    let ok_button = Button::default().with_label("&OK");
+    let cancel_button = Button::default().with_label("&Cancel");
+    Frame::default(); // pad right of buttons
+    row.set_size(&ok_button, BUTTON_WIDTH);
+    row.set_size(&cancel_button, BUTTON_WIDTH);
+    row.end();
+    (row, ok_button, cancel_button)
+}
+
+const WIDTH: i32 = 280;
+const HEIGHT: i32 = 250;
+const ROW_HEIGHT: i32 = 30;
@@ -0,0 +1,248 @@
+// Copyright © 2021-22 Mark Summerfield. All rights reserved.
+// License: GPLv3
+
+use std::collections::HashMap;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Style {
+    Bold,
+    Italic,
+    BoldItalic,
+    Script,
+    Fraktur,
+    DoubleStruck,
+    SansSerif,
+    SansSerifBold,
+    Monospace,
+    Fullwidth,
+    SmallCaps,
+    Circled,
+    Superscript,
+    Subscript,
+}
+
+impl Style {
+    pub const ALL: [Style; 14] = [
+        Style::Bold,
+        Style::Italic,
+        Style::BoldItalic,
+        Style::Script,
+        Style::Fraktur,
+        Style::DoubleStruck,
+        Style::SansSerif,
+        Style::SansSerifBold,
+        Style::Monospace,
+        Style::Fullwidth,
+        Style::SmallCaps,
+        Style::Circled,
+        Style::Superscript,
+        Style::Subscript,
+    ];
+
+    // The text used in menus (with an accelerator)
+    pub fn label(&self) -> &'static str {
+        match self {
+            Style::Bold => "&Bold",
+            Style::Italic => "&Italic",
+            Style::BoldItalic => "Bold Ita&lic",
+            Style::Script => "&Script",
+            Style::Fraktur => "&Fraktur",
+            Style::DoubleStruck => "&Double-Struck",
+            Style::SansSerif => "Sans-S&erif",
+            Style::SansSerifBold => "Sans-Serif B&old",
+            Style::Monospace => "&Monospace",
+            Style::Fullwidth => "F&ullwidth",
+            Style::SmallCaps => "Small &Caps",
+            Style::Circled => "Ci&rcled",
+            Style::Superscript => "Su&perscript",
+            Style::Subscript => "Subscrip&t",
+        }
+    }
+
+    // The name prefixes of the styled characters in order of preference;
+    // the later ones fill the gaps left by the earlier ones (e.g., the
+    // MATHEMATICAL SCRIPT CAPITAL B slot is empty because the character
+    // was already encoded as SCRIPT CAPITAL B)
+    fn prefixes(&self) -> &'static [&'static str] {
+        match self {
+            Style::Bold => &["MATHEMATICAL BOLD"],
+            Style::Italic => &["MATHEMATICAL ITALIC"],
+            Style::BoldItalic => &["MATHEMATICAL BOLD ITALIC"],
+            Style::Script => &["MATHEMATICAL SCRIPT", "SCRIPT"],
+            Style::Fraktur => &["MATHEMATICAL FRAKTUR", "BLACK-LETTER"],
+            Style::DoubleStruck => {
+                &["MATHEMATICAL DOUBLE-STRUCK", "DOUBLE-STRUCK"]
+            }
+            Style::SansSerif => &["MATHEMATICAL SANS-SERIF"],
+            Style::SansSerifBold => &["MATHEMATICAL SANS-SERIF BOLD"],
+            Style::Monospace => &["MATHEMATICAL MONOSPACE"],
+            Style::Fullwidth => &["FULLWIDTH"],
+            Style::SmallCaps => &["LATIN LETTER SMALL CAPITAL"],
+            Style::Circled => &["CIRCLED"],
+            Style::Superscript => &["SUPERSCRIPT"],
+            Style::Subscript => &["SUBSCRIPT", "LATIN SUBSCRIPT"],
+        }
+    }
+
+    // The one gap whose character's name doesn't follow the pattern
+    fn exceptions(&self) -> &'static [(char, &'static str)] {
+        match self {
+            Style::Italic => &[('h', "PLANCK CONSTANT")],
+            _ => &[],
+        }
+    }
+}
+
+// Maps ASCII letters and digits to their styled equivalents for every
+// style; built from the names in the character data
+#[derive(Clone, Debug, Default)]
+pub struct Styles {
+    maps: HashMap<Style, HashMap<char, char>>,
+}
+
+impl Styles {
+    pub fn new(chardata: &str) -> Self {
+        let mut ranked: HashMap<Style, HashMap<char, (usize, char)>> =
+            HashMap::new();
+        for line in chardata.lines() {
+            let mut cols = line.split('\t');
+            let (cp, name) = match (cols.next(), cols.next()) {
+                (Some(cp), Some(name)) => (cp, name),
+                _ => continue,
+            };
+            let styled = match u32::from_str_radix(cp, 16)
+                .ok()
+                .and_then(char::from_u32)
+            {
+                Some(styled) => styled,
+                None => continue,
+            };
+            for style in Style::ALL {
+                for (i, prefix) in style.prefixes().iter().enumerate() {
+                    let plain = name
+                        .strip_prefix(prefix)
+                        .and_then(|rest| rest.strip_prefix(' '))
+                        .and_then(plain_for);
+                    if let Some(plain) = plain {
+                        let map = ranked.entry(style).or_default();
+                        match map.get(&plain) {
+                            Some((rank, _)) if *rank <= i => {}
+                            _ => {
+                                map.insert(plain, (i, styled));
+                            }
+                        }
+                    }
+                }
+                for (plain, exception) in style.exceptions() {
+                    if name == *exception {
+                        ranked
+                            .entry(style)
+                            .or_default()
+                            .insert(*plain, (usize::MAX, styled));
+                    }
+                }
+            }
+        }
+        let maps = ranked
+            .into_iter()
+            .map(|(style, map)| {
+                (style, map.into_iter().map(|(k, (_, v))| (k, v)).collect())
+            })
+            .collect();
+        Self { maps }
+    }
+
+    // Characters with no styled equivalent are passed through unchanged
+    pub fn apply(&self, style: Style, text: &str) -> String {
+        match self.maps.get(&style) {
+            Some(map) => {
+                text.chars().map(|c| *map.get(&c).unwrap_or(&c)).collect()
+            }
+            None => text.to_string(),
+        }
+    }
+}
+
+// Returns the plain ASCII character for the rest of a styled character's
+// name (after its style prefix), e.g., "CAPITAL A" → 'A', "SMALL A" → 'a',
+// "LATIN SMALL LETTER A" → 'a', "DIGIT ONE" → '1', "ONE" → '1'; a bare
+// letter, e.g., "A" (as in LATIN LETTER SMALL CAPITAL A) → 'a'
+fn plain_for(rest: &str) -> Option<char> {
+    let words = rest.split(' ').collect::<Vec<&str>>();
+    match words.as_slice() {
+        ["DIGIT", digit] | [digit] if digit_for(digit).is_some() => {
+            digit_for(digit)
+        }
+        [case, letter]
+        | [case, "LETTER", letter]
+        | ["LATIN", case, "LETTER", letter] => cased_letter(case, letter),
+        [letter] => cased_letter("SMALL", letter),
+        _ => None,
+    }
+}
+
+fn cased_letter(case: &str, letter: &str) -> Option<char> {
+    let mut chars = letter.chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), None) if c.is_ascii_uppercase() => match case {
+            "CAPITAL" => Some(c),
+            "SMALL" => Some(c.to_ascii_lowercase()),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+fn digit_for(word: &str) -> Option<char> {
+    DIGITS
+        .iter()
+        .position(|digit| *digit == word)
+        .and_then(|i| char::from_digit(i as u32, 10))
+}
+
+const DIGITS: [&str; 10] = [
+    "ZERO", "ONE", "TWO", "THREE", "FOUR", "FIVE", "SIX", "SEVEN", "EIGHT",
+    "NINE",
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    static CHARDATA: &str = "1D400\tMATHEMATICAL BOLD CAPITAL A
+1D41A\tMATHEMATICAL BOLD SMALL A
+1D468\tMATHEMATICAL BOLD ITALIC CAPITAL A
+1D7CE\tMATHEMATICAL BOLD DIGIT ZERO
+1D44E\tMATHEMATICAL ITALIC SMALL A
+210E\tPLANCK CONSTANT
+1D49C\tMATHEMATICAL SCRIPT CAPITAL A
+212C\tSCRIPT CAPITAL B
+1D00\tLATIN LETTER SMALL CAPITAL A
+2460\tCIRCLED DIGIT ONE
+";
+
+    #[test]
+    fn styles_come_from_the_names() {
+        let styles = Styles::new(CHARDATA);
+        assert_eq!(styles.apply(Style::Bold, "Aa0!"), "𝐀𝐚𝟎!");
+        assert_eq!(styles.apply(Style::BoldItalic, "A"), "𝑨");
+        assert_eq!(styles.apply(Style::Italic, "ah"), "𝑎ℎ");
+        assert_eq!(styles.apply(Style::Script, "ABC"), "𝒜ℬC");
+        assert_eq!(styles.apply(Style::SmallCaps, "a"), "ᴀ");
+        assert_eq!(styles.apply(Style::Circled, "12"), "①2");
+        assert_eq!(styles.apply(Style::Fraktur, "A"), "A");
+    }
+
+    #[test]
+    fn plain_for_name_endings() {
+        assert_eq!(plain_for("CAPITAL A"), Some('A'));
+        assert_eq!(plain_for("SMALL A"), Some('a'));
+        assert_eq!(plain_for("SMALL LETTER A"), Some('a'));
+        assert_eq!(plain_for("LATIN SMALL LETTER A"), Some('a'));
+        assert_eq!(plain_for("DIGIT ONE"), Some('1'));
+        assert_eq!(plain_for("ONE"), Some('1'));
+        assert_eq!(plain_for("A"), Some('a'));
+        assert_eq!(plain_for("ITALIC CAPITAL A"), None);
+        assert_eq!(plain_for("CAPITAL AE"), None);
+    }
+}
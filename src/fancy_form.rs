@@ -0,0 +1,141 @@
+// Copyright © 2021-22 Mark Summerfield. All rights reserved.
+// License: GPLv3
+
+use crate::fancy::{Style, Styles};
+use crate::fixed::{
+    Action, APPNAME, BUTTON_HEIGHT, BUTTON_WIDTH, ICON, PAD,
+};
+use fltk::{
+    app, app::Sender, browser::HoldBrowser, button::Button, enums::Font,
+    frame::Frame, group::Flex, image::SvgImage, prelude::*, window::Window,
+};
+
+pub struct Form {
+    form: Window,
+    browser: HoldBrowser,
+}
+
+impl Form {
+    pub fn new(
+        sender: Sender<Action>,
+        font: usize,
+        styles: &Styles,
+        text: &str,
+    ) -> Self {
+        let mut form = make_form();
+        let mut vbox = Flex::default().size_of_parent().column();
+        vbox.set_margin(PAD);
+        vbox.set_pad(PAD);
+        let mut browser = HoldBrowser::default();
+        browser.set_column_char('\t');
+        browser.set_column_widths(&COLUMN_WIDTHS);
+        browser.set_text_font(Font::by_index(font));
+        browser.set_tooltip("Double-click a style to apply it to the output editor's selected text (or all its text if none is selected)");
+        let mut row = Flex::default().row();
+        row.set_pad(PAD);
+        Frame::default();
+        let mut apply_button = Button::default().with_label("&Apply");
+        let mut close_button = Button::default().with_label("&Close");
+        row.set_size(&apply_button, BUTTON_WIDTH);
+        row.set_size(&close_button, BUTTON_WIDTH);
+        row.end();
+        vbox.set_size(&row, BUTTON_HEIGHT);
+        vbox.end();
+        form.end();
+        browser.set_callback(move |browser| {
+            if app::event_clicks() {
+                send_style(browser, sender);
+            }
+        });
+        apply_button.set_callback({
+            let browser = browser.clone();
+            move |_| {
+                send_style(&browser, sender);
+            }
+        });
+        close_button.set_callback({
+            let mut form = form.clone();
+            move |_| {
+                form.hide();
+            }
+        });
+        form.show();
+        let mut fancy_form = Self { form, browser };
+        fancy_form.populate(styles, text);
+        fancy_form
+    }
+
+    pub fn show(&mut self) {
+        self.form.show();
+    }
+
+    pub fn shown(&self) -> bool {
+        self.form.shown()
+    }
+
+    pub fn update(&mut self, styles: &Styles, text: &str) {
+        if self.form.shown() {
+            self.populate(styles, text);
+        }
+    }
+
+    fn populate(&mut self, styles: &Styles, text: &str) {
+        let line = self.browser.value();
+        self.browser.clear();
+        let text = sample(text);
+        for style in Style::ALL {
+            let preview = styles.apply(style, &text);
+            self.browser.add(&format!(
+                "{}\t@.{preview}",
+                style.label().replace('&', "")
+            ));
+        }
+        if line > 0 {
+            self.browser.select(line);
+        }
+    }
+}
+
+impl Drop for Form {
+    fn drop(&mut self) {
+        app::delete_widget(self.form.clone());
+    }
+}
+
+fn make_form() -> Window {
+    let image = SvgImage::from_data(ICON).unwrap();
+    let mut form = Window::default()
+        .with_size(WIDTH, HEIGHT)
+        .with_label(&format!("Fancy Text — {APPNAME}"));
+    if let Some(window) = app::first_window() {
+        form.set_pos(window.x() + 50, window.y() + 100);
+    }
+    form.set_icon(Some(image));
+    form.make_resizable(true);
+    form
+}
+
+fn send_style(browser: &HoldBrowser, sender: Sender<Action>) {
+    let line = browser.value();
+    if line > 0 {
+        if let Some(style) = Style::ALL.get(line as usize - 1) {
+            sender.send(Action::Fancy(*style));
+        }
+    }
+}
+
+// Returns the first line of the text (or a sample if it is empty) short
+// enough to preview
+fn sample(text: &str) -> String {
+    let line = text.lines().find(|line| !line.trim().is_empty());
+    match line {
+        Some(line) => line.chars().take(SAMPLE_MAX).collect(),
+        None => SAMPLE.to_string(),
+    }
+}
+
+const WIDTH: i32 = 640;
+const HEIGHT: i32 = 440;
+const COLUMN_WIDTHS: [i32; 1] = [140];
+const SAMPLE: &str = "Hello World 0123456789";
+const SAMPLE_MAX: usize = 40;
@@ -0,0 +1,486 @@
+// Copyright © 2021-22 Mark Summerfield. All rights reserved.
+// License: GPLv3
+
+use super::CONFIG;
+use crate::fixed::{APPNAME, BUTTON_HEIGHT, BUTTON_WIDTH, ICON, PAD};
+use crate::util;
+use fltk::{
+    app,
+    browser::HoldBrowser,
+    button::Button,
+    dialog,
+    enums::{Event, Font},
+    frame::Frame,
+    group::Flex,
+    image::SvgImage,
+    prelude::*,
+    window::Window,
+};
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+
+pub struct Form {
+    form: Window,
+    pub ok: Rc<RefCell<bool>>,
+}
+
+impl Form {
+    pub fn new(font: usize) -> Self {
+        let ok = Rc::from(RefCell::from(false));
+        let mut form = make_form();
+        let mut vbox = Flex::default().size_of_parent().column();
+        vbox.set_margin(PAD);
+        vbox.set_pad(PAD);
+        let mut row = Flex::default().row();
+        row.set_pad(PAD);
+        let mut groups_browser = HoldBrowser::default();
+        groups_browser.set_tooltip(
+            "The favorites groups in menu order; drag a group to move it",
+        );
+        let (group_column, mut group_buttons) = make_group_buttons();
+        let mut chars_browser = HoldBrowser::default();
+        chars_browser.set_column_char('\t');
+        chars_browser.set_column_widths(&COLUMN_WIDTHS);
+        chars_browser.set_text_font(Font::by_index(font));
+        chars_browser.set_tooltip(
+            "The group's characters in menu order; drag a character to move it",
+        );
+        let (char_column, mut char_buttons) = make_char_buttons();
+        row.set_size(&group_column, BUTTON_WIDTH + PAD * 8);
+        row.set_size(&char_column, BUTTON_WIDTH + PAD * 8);
+        row.end();
+        let (button_row, mut ok_button, mut cancel_button) =
+            make_bottom_row();
+        vbox.set_size(&button_row, BUTTON_HEIGHT);
+        vbox.end();
+        form.end();
+        form.make_modal(true);
+        let favorites = CONFIG.get().read().unwrap().favorites.clone();
+        let view = Rc::from(RefCell::from(View {
+            favorites,
+            groups_browser,
+            chars_browser,
+        }));
+        view.borrow_mut().populate_groups(1);
+        add_event_handlers(&view, &mut group_buttons, &mut char_buttons);
+        ok_button.set_callback({
+            let ok = Rc::clone(&ok);
+            let view = Rc::clone(&view);
+            let mut form = form.clone();
+            move |_| {
+                *ok.borrow_mut() = true;
+                let mut config = CONFIG.get().write().unwrap();
+                config.favorites = view.borrow().favorites.clone();
+                form.hide();
+            }
+        });
+        cancel_button.set_callback({
+            let mut form = form.clone();
+            move |_| {
+                form.hide();
+            }
+        });
+        form.show();
+        while form.shown() {
+            app::wait();
+        }
+        Self { form, ok }
+    }
+}
+
+impl Drop for Form {
+    fn drop(&mut self) {
+        app::delete_widget(self.form.clone());
+    }
+}
+
+struct View {
+    favorites: Vec<(String, Vec<char>)>,
+    groups_browser: HoldBrowser,
+    chars_browser: HoldBrowser,
+}
+
+impl View {
+    // Returns the index of the selected group
+    fn group(&self) -> Option<usize> {
+        let line = self.groups_browser.value();
+        if line > 0 && line as usize <= self.favorites.len() {
+            Some(line as usize - 1)
+        } else {
+            None
+        }
+    }
+
+    // Returns the indexes of the selected group and character
+    fn char(&self) -> Option<(usize, usize)> {
+        let group = self.group()?;
+        let line = self.chars_browser.value();
+        if line > 0 && line as usize <= self.favorites[group].1.len() {
+            Some((group, line as usize - 1))
+        } else {
+            None
+        }
+    }
+
+    fn populate_groups(&mut self, line: i32) {
+        self.groups_browser.clear();
+        for (name, chars) in self.favorites.iter() {
+            self.groups_browser.add(&format!("@.{name} ({})", chars.len()));
+        }
+        if !self.favorites.is_empty() {
+            self.groups_browser
+                .select(line.clamp(1, self.favorites.len() as i32));
+        }
+        self.populate_chars(1);
+    }
+
+    fn populate_chars(&mut self, line: i32) {
+        self.chars_browser.clear();
+        if let Some(group) = self.group() {
+            let chars = &self.favorites[group].1;
+            for c in chars.iter() {
+                self.chars_browser
+                    .add(&format!("@.{c}\tU+{:04X}", *c as u32));
+            }
+            if !chars.is_empty() {
+                self.chars_browser
+                    .select(line.clamp(1, chars.len() as i32));
+            }
+        }
+    }
+
+    fn new_group(&mut self) {
+        if let Some(name) = self.ask_name("New favorites group", "") {
+            let line = match self.group() {
+                Some(group) => group + 1,
+                None => self.favorites.len(),
+            };
+            self.favorites.insert(line, (name, vec![]));
+            self.populate_groups(line as i32 + 1);
+        }
+    }
+
+    fn rename_group(&mut self) {
+        if let Some(group) = self.group() {
+            let old = self.favorites[group].0.clone();
+            if let Some(name) =
+                self.ask_name("Rename favorites group", &old)
+            {
+                self.favorites[group].0 = name;
+                self.populate_groups(group as i32 + 1);
+            }
+        }
+    }
+
+    // Returns the name (made safe for use in menus) if it is new
+    fn ask_name(&self, message: &str, old: &str) -> Option<String> {
+        dialog::message_title(&format!("Favorites — {APPNAME}"));
+        let name = util::menu_name(&dialog::input_default(message, old)?);
+        if name.is_empty() || name == old {
+            return None;
+        }
+        if self.favorites.iter().any(|(n, _)| *n == name) {
+            dialog::alert_default(&format!(
+                "There is already a group called “{name}”"
+            ));
+            return None;
+        }
+        Some(name)
+    }
+
+    fn delete_group(&mut self) {
+        if let Some(group) = self.group() {
+            self.favorites.remove(group);
+            self.populate_groups(group as i32 + 1);
+        }
+    }
+
+    fn add_chars(&mut self) {
+        if let Some(group) = self.group() {
+            dialog::message_title(&format!("Favorites — {APPNAME}"));
+            if let Some(text) = dialog::input_default(
+                &format!(
+                    "Characters to add to “{}”",
+                    self.favorites[group].0
+                ),
+                "",
+            ) {
+                let chars = &mut self.favorites[group].1;
+                for c in text.chars() {
+                    if !c.is_whitespace()
+                        && !c.is_control()
+                        && !chars.contains(&c)
+                    {
+                        chars.push(c);
+                    }
+                }
+                let line = chars.len() as i32;
+                self.populate_groups(group as i32 + 1);
+                self.populate_chars(line);
+            }
+        }
+    }
+
+    fn remove_char(&mut self) {
+        if let Some((group, i)) = self.char() {
+            self.favorites[group].1.remove(i);
+            self.populate_groups(group as i32 + 1);
+            self.populate_chars(i as i32 + 1);
+        }
+    }
+
+    fn move_group(&mut self, from: usize, to: usize) {
+        if from != to && to < self.favorites.len() {
+            let item = self.favorites.remove(from);
+            self.favorites.insert(to, item);
+            self.populate_groups(to as i32 + 1);
+        }
+    }
+
+    fn move_char(&mut self, from: usize, to: usize) {
+        if let Some(group) = self.group() {
+            let chars = &mut self.favorites[group].1;
+            if from != to && to < chars.len() {
+                let c = chars.remove(from);
+                chars.insert(to, c);
+                self.populate_chars(to as i32 + 1);
+            }
+        }
+    }
+}
+
+struct GroupButtons {
+    new_button: Button,
+    rename_button: Button,
+    delete_button: Button,
+    up_button: Button,
+    down_button: Button,
+}
+
+struct CharButtons {
+    add_button: Button,
+    remove_button: Button,
+    up_button: Button,
+    down_button: Button,
+}
+
+fn make_form() -> Window {
+    let image = SvgImage::from_data(ICON).unwrap();
+    let mut form = Window::default()
+        .with_size(WIDTH, HEIGHT)
+        .with_label(&format!("Favorites — {APPNAME}"));
+    if let Some(window) = app::first_window() {
+        form.set_pos(window.x() + 50, window.y() + 100);
+    }
+    form.set_icon(Some(image));
+    form.make_resizable(true);
+    form
+}
+
+fn make_group_buttons() -> (Flex, GroupButtons) {
+    let mut column = Flex::default().column();
+    column.set_pad(PAD);
+    let new_button = Button::default().with_label("&New Group…");
+    let rename_button = Button::default().with_label("Rena&me Group…");
+    let delete_button = Button::default().with_label("De&lete Group");
+    let up_button = Button::default().with_label("Move Group &Up");
+    let down_button = Button::default().with_label("Move Group &Down");
+    Frame::default();
+    for button in [
+        &new_button,
+        &rename_button,
+        &delete_button,
+        &up_button,
+        &down_button,
+    ] {
+        column.set_size(button, BUTTON_HEIGHT);
+    }
+    column.end();
+    (
+        column,
+        GroupButtons {
+            new_button,
+            rename_button,
+            delete_button,
+            up_button,
+            down_button,
+        },
+    )
+}
+
+fn make_char_buttons() -> (Flex, CharButtons) {
+    let mut column = Flex::default().column();
+    column.set_pad(PAD);
+    let mut add_button = Button::default().with_label("&Add Characters…");
+    add_button.set_tooltip(
+        "Type or paste characters to add to the group; characters can also be added from the search results table's context menu",
+    );
+    let remove_button = Button::default().with_label("&Remove Character");
+    let up_button = Button::default().with_label("Move Character U&p");
+    let down_button = Button::default().with_label("Move Character Do&wn");
+    Frame::default();
+    for button in [&add_button, &remove_button, &up_button, &down_button] {
+        column.set_size(button, BUTTON_HEIGHT);
+    }
+    column.end();
+    (
+        column,
+        CharButtons { add_button, remove_button, up_button, down_button },
+    )
+}
+
+fn make_bottom_row() -> (Flex, Button, Button) {
+    let mut row = Flex::default().row();
+    row.set_pad(PAD);
+    Frame::default(); // pad left of buttons
+    let ok_button = Button::default().with_label("&OK");
+    let cancel_button = Button::default().with_label("&Cancel");
+    Frame::default(); // pad right of buttons
+    row.set_size(&ok_button, BUTTON_WIDTH);
+    row.set_size(&cancel_button, BUTTON_WIDTH);
+    row.end();
+    (row, ok_button, cancel_button)
+}
+
+fn add_event_handlers(
+    view: &Rc<RefCell<View>>,
+    group_buttons: &mut GroupButtons,
+    char_buttons: &mut CharButtons,
+) {
+    add_browser_event_handlers(view);
+    group_buttons.new_button.set_callback({
+        let view = Rc::clone(view);
+        move |_| {
+            view.borrow_mut().new_group();
+        }
+    });
+    group_buttons.rename_button.set_callback({
+        let view = Rc::clone(view);
+        move |_| {
+            view.borrow_mut().rename_group();
+        }
+    });
+    group_buttons.delete_button.set_callback({
+        let view = Rc::clone(view);
+        move |_| {
+            view.borrow_mut().delete_group();
+        }
+    });
+    group_buttons.up_button.set_callback({
+        let view = Rc::clone(view);
+        move |_| {
+            let mut view = view.borrow_mut();
+            if let Some(group) = view.group() {
+                if group > 0 {
+                    view.move_group(group, group - 1);
+                }
+            }
+        }
+    });
+    group_buttons.down_button.set_callback({
+        let view = Rc::clone(view);
+        move |_| {
+            let mut view = view.borrow_mut();
+            if let Some(group) = view.group() {
+                view.move_group(group, group + 1);
+            }
+        }
+    });
+    char_buttons.add_button.set_callback({
+        let view = Rc::clone(view);
+        move |_| {
+            view.borrow_mut().add_chars();
+        }
+    });
+    char_buttons.remove_button.set_callback({
+        let view = Rc::clone(view);
+        move |_| {
+            view.borrow_mut().remove_char();
+        }
+    });
+    char_buttons.up_button.set_callback({
+        let view = Rc::clone(view);
+        move |_| {
+            let mut view = view.borrow_mut();
+            if let Some((_, i)) = view.char() {
+                if i > 0 {
+                    view.move_char(i, i - 1);
+                }
+            }
+        }
+    });
+    char_buttons.down_button.set_callback({
+        let view = Rc::clone(view);
+        move |_| {
+            let mut view = view.borrow_mut();
+            if let Some((_, i)) = view.char() {
+                view.move_char(i, i + 1);
+            }
+        }
+    });
+}
+
+// A hold browser's selection follows the mouse while dragging, so a drag
+// moves the line where it started to the line where it is released. These
+// handlers run before the browser's own, so the line where a drag started
+// is only selected by the first drag event (at a push the previous
+// selection is still current), and a plain click moves nothing
+fn add_browser_event_handlers(view: &Rc<RefCell<View>>) {
+    let mut borrowed = view.borrow_mut();
+    let dragged_group = Rc::new(Cell::new(0));
+    borrowed.groups_browser.handle({
+        let view = Rc::clone(view);
+        move |browser, event| {
+            match event {
+                Event::Push => dragged_group.set(0),
+                Event::Drag if dragged_group.get() == 0 => {
+                    dragged_group.set(browser.value())
+                }
+                Event::Released => {
+                    let from = dragged_group.get();
+                    dragged_group.set(0);
+                    let to = browser.value();
+                    if from > 0 && to > 0 && from != to {
+                        view.borrow_mut()
+                            .move_group(from as usize - 1, to as usize - 1);
+                    }
+                }
+                _ => {}
+            }
+            false
+        }
+    });
+    borrowed.groups_browser.set_callback({
+        let view = Rc::clone(view);
+        move |_| {
+            view.borrow_mut().populate_chars(1);
+        }
+    });
+    let dragged_char = Rc::new(Cell::new(0));
+    borrowed.chars_browser.handle({
+        let view = Rc::clone(view);
+        move |browser, event| {
+            match event {
+                Event::Push => dragged_char.set(0),
+                Event::Drag if dragged_char.get() == 0 => {
+                    dragged_char.set(browser.value())
+                }
+                Event::Released => {
+                    let from = dragged_char.get();
+                    dragged_char.set(0);
+                    let to = browser.value();
+                    if from > 0 && to > 0 && from != to {
+                        view.borrow_mut()
+                            .move_char(from as usize - 1, to as usize - 1);
+                    }
+                }
+                _ => {}
+            }
+            false
+        }
+    });
+}
+
+const WIDTH: i32 = 640;
+const HEIGHT: i32 = 400;
+const COLUMN_WIDTHS: [i32; 1] = [50];
@@ -1,6 +1,9 @@
 // Copyright © 2021-22 Mark Summerfield. All rights reserved.
 // License: GPLv3
 
+use crate::escape::Format;
+use crate::fancy::Style;
+use crate::transform::Transform;
 use crate::util::capitalize_first;
 use chrono::prelude::*;
 use fltk::app;
@@ -9,17 +12,36 @@ use std::env;
 pub static APPNAME: &str = "CharFind";
 pub static VERSION: &str = "1.2.5";
 pub static CHARDATA: &[u8] = include_bytes!("../data/chardata.txt.gz");
+pub static BLOCKS: &str = include_str!("../data/blocks.txt");
+pub static COMBINING: &[u8] = include_bytes!("../data/combining.txt.gz");
 pub static HELP_HTML: &str = include_str!("../data/help.html");
+pub static HELP_KEYS_MARKER: &str = "<!-- KEYS -->";
+pub static EXCHANGE_FILTER: &str = "JSON Files\t*.json";
 pub const ICON: &str = include_str!("../images/charfind.svg");
 pub const AUTO_MENU_SIZE: usize = 26;
+pub const HISTORY_MAX: usize = 1000;
+pub const SNIPPET_PREVIEW_MAX: usize = 24;
+pub const UNDO_MAX: usize = 100;
+pub const COMPLETION_MAX: usize = 20;
+pub const COMPLETION_MIN: usize = 2; // Characters typed after the prefix
+pub const COMPLETION_WIDTH: i32 = 320;
+pub const PROBLEMS_MAX: usize = 20;
+pub const COMPLETION_PREFIX_DEFAULT: &str = ":";
 pub const PAD: i32 = 6;
 pub const WINDOW_WIDTH_MIN: i32 = 400;
-pub const WINDOW_HEIGHT_MIN: i32 = 440;
+pub const WINDOW_HEIGHT_MIN: i32 = 520;
 pub const ROW_HEIGHT: i32 = 40;
+pub const OUTPUT_HEIGHT: i32 = 100;
 pub const BUTTON_HEIGHT: i32 = 30;
 pub const BUTTON_WIDTH: i32 = 90;
 pub const SCALE_MIN: f32 = 0.5;
 pub const SCALE_MAX: f32 = 3.5;
+pub const COMPARE_SIZE_MIN: i32 = 8;
+pub const COMPARE_SIZE_MAX: i32 = 240;
+pub const COMPARE_SIZE_DEFAULT: i32 = 48;
+pub const GLYPH_SIZE_MIN: i32 = 16;
+pub const GLYPH_SIZE_MAX: i32 = 600;
+pub const GLYPH_SIZE_DEFAULT: i32 = 240;
 pub static MENU_CHARS: [char; 35] = [
     '1', '2', '3', '4', '5', '6', '7', '8', '9', 'A', 'B', 'C', 'D', 'E',
     'F', 'G', 'H', 'I', 'J', 'K', 'L', 'M', 'N', 'O', 'P', 'Q', 'R', 'S',
@@ -29,21 +51,62 @@ pub static MENU_CHARS: [char; 35] = [
 #[derive(Copy, Clone, Debug)]
 pub enum Action {
     About,
+    AccentPicker,
     AddChar(char),
-    AddFromTable,
+    AddSelected,
     MaybeAddFromTable,
     Copy,
+    CopyAs(Format),
     Clear,
+    Compare,
+    Complete,
+    Composer,
+    DeleteSnippet(usize),
+    Export,
+    FocusToSearch,
     FocusToSearchResults,
+    GlyphInspector,
+    Fancy(Style),
+    Favorites,
+    FancyPreview,
     Help,
+    HideCompletion,
+    HistoryPanel,
+    Import,
+    InsertComposed(bool),
+    InsertSnippet(usize),
+    Inspect,
     Options,
+    OutputChanged,
+    PopupMenu(Popup),
     Quit,
+    ResultsMenu,
+    SaveSnippet,
     Search,
     PopupSearches,
+    Redo,
+    RemoveFromHistory(char),
     SearchFor(i32),
+    SelectAll,
+    SelectChar(char),
+    ToggleCodePoint,
+    Transform(Transform),
+    Undo,
+    Unescape,
     UpdatePreview,
 }
 
+// The main window's menu buttons that a key binding can pop up
+#[derive(Copy, Clone, Debug)]
+pub enum Popup {
+    History,
+    Favorites,
+    Snippets,
+    CopyAs,
+    Transform,
+    Tools,
+}
+
 pub fn about_html() -> String {
     let year = Local::today().year();
     let year = if year == 2022 {
@@ -0,0 +1,181 @@
+// Copyright © 2021-22 Mark Summerfield. All rights reserved.
+// License: GPLv3
+
+use fltk::{app, enums::Font};
+use std::{cmp, collections::HashMap, process::Command};
+
+type Ranges = Vec<(u32, u32)>;
+
+// Which installed font families can render which code points. The
+// coverage comes from fontconfig so is only available where fc-list is
+// installed (e.g., Linux); elsewhere every font is assumed to cover every
+// character, i.e., the behavior is as it was before coverage existed.
+#[derive(Clone, Debug, Default)]
+pub struct Fonts {
+    families: Vec<(String, Ranges)>,
+    index_for_family: HashMap<String, usize>,
+    family_for_index: Vec<String>,
+}
+
+impl Fonts {
+    pub fn new() -> Self {
+        let mut fonts = Fonts::default();
+        let count = app::fonts().len();
+        for i in 0..count {
+            let family = normalized(&Font::by_index(i).get_name());
+            fonts.index_for_family.entry(family.clone()).or_insert(i);
+            fonts.family_for_index.push(family);
+        }
+        fonts.families = read_coverage();
+        fonts
+    }
+
+    pub fn has_coverage(&self) -> bool {
+        !self.families.is_empty()
+    }
+
+    // Returns the FLTK font index of the preferred font if it covers the
+    // code point, otherwise of the first covering font FLTK knows about,
+    // or None if no installed font covers it
+    pub fn font_for(&self, cp: u32, preferred: usize) -> Option<usize> {
+        if !self.has_coverage() {
+            return Some(preferred);
+        }
+        if self.font_covers(preferred, cp) {
+            return Some(preferred);
+        }
+        for (family, ranges) in self.families.iter() {
+            if covers(ranges, cp) {
+                if let Some(i) = self.index_for_family.get(family) {
+                    return Some(*i);
+                }
+            }
+        }
+        None
+    }
+
+    // Returns true if the font has a glyph for the code point (or if this
+    // can't be determined)
+    pub fn font_covers(&self, font: usize, cp: u32) -> bool {
+        if !self.has_coverage() {
+            return true;
+        }
+        match self.family_for_index.get(font) {
+            Some(family) => self.family_covers(family, cp),
+            None => false,
+        }
+    }
+
+    // Returns the display names of every installed font family that has a
+    // glyph for the code point
+    pub fn families_for(&self, cp: u32) -> Vec<String> {
+        self.families
+            .iter()
+            .filter(|(_, ranges)| covers(ranges, cp))
+            .map(|(family, _)| self.display_name(family))
+            .collect()
+    }
+
+    fn family_covers(&self, family: &str, cp: u32) -> bool {
+        self.families
+            .iter()
+            .any(|(name, ranges)| name == family && covers(ranges, cp))
+    }
+
+    fn display_name(&self, family: &str) -> String {
+        match self.index_for_family.get(family) {
+            Some(i) => Font::by_index(*i).get_name().trim().to_string(),
+            None => family.to_string(),
+        }
+    }
+}
+
+// Returns the display name and FLTK font index of every regular (i.e., not
+// bold or italic) font
+pub fn regular_fonts() -> Vec<(String, usize)> {
+    let mut names = Vec::new();
+    for i in 0..app::fonts().len() {
+        if let Some(name) = Font::by_index(i).get_name().strip_prefix(' ') {
+            let name = name.to_string();
+            if !names.iter().any(|(other, _)| *other == name) {
+                names.push((name, i));
+            }
+        }
+    }
+    names
+}
+
+// FLTK prefixes font names with a space (or B, I, P for bold, italic, bold
+// italic) so names are compared uppercased with all spaces removed
+pub fn normalized(name: &str) -> String {
+    name.to_uppercase().replace(' ', "")
+}
+
+fn covers(ranges: &Ranges, cp: u32) -> bool {
+    ranges
+        .binary_search_by(|&(start, end)| {
+            if end < cp {
+                cmp::Ordering::Less
+            } else if start > cp {
+                cmp::Ordering::Greater
+            } else {
+                cmp::Ordering::Equal
+            }
+        })
+        .is_ok()
+}
+
+fn read_coverage() -> Vec<(String, Ranges)> {
+    let mut ranges_for_family: HashMap<String, Ranges> = HashMap::new();
+    if let Ok(output) = Command::new("fc-list")
+        .args(["--format", "%{family[0]}\\t%{charset}\\n"])
+        .output()
+    {
+        for line in String::from_utf8_lossy(&output.stdout).lines() {
+            if let Some((family, charset)) = line.split_once('\t') {
+                let ranges = ranges_for_family
+                    .entry(normalized(family))
+                    .or_insert_with(Ranges::new);
+                ranges.extend(charset.split_whitespace().filter_map(range));
+            }
+        }
+    }
+    let mut families = ranges_for_family
+        .into_iter()
+        .map(|(family, ranges)| (family, coalesced(ranges)))
+        .collect::<Vec<(String, Ranges)>>();
+    // Prefer monospaced fallbacks to match the results table
+    families.sort_by(|(a, _), (b, _)| {
+        (!a.contains("MONO"), a).cmp(&(!b.contains("MONO"), b))
+    });
+    families
+}
+
+// fontconfig charsets are space-separated hex code points or ranges, e.g.,
+// "20-7e a0-17f 2022"
+fn range(text: &str) -> Option<(u32, u32)> {
+    if let Some((start, end)) = text.split_once('-') {
+        Some((
+            u32::from_str_radix(start, 16).ok()?,
+            u32::from_str_radix(end, 16).ok()?,
+        ))
+    } else {
+        let cp = u32::from_str_radix(text, 16).ok()?;
+        Some((cp, cp))
+    }
+}
+
+fn coalesced(mut ranges: Ranges) -> Ranges {
+    ranges.sort_unstable();
+    let mut result = Ranges::with_capacity(ranges.len());
+    for (start, end) in ranges {
+        if let Some(last) = result.last_mut() {
+            if start <= last.1.saturating_add(1) {
+                last.1 = last.1.max(end);
+                continue;
+            }
+        }
+        result.push((start, end));
+    }
+    result
+}
@@ -0,0 +1,320 @@
+// Copyright © 2021-22 Mark Summerfield. All rights reserved.
+// License: GPLv3
+
+use super::CONFIG;
+use crate::fixed::{
+    APPNAME, BUTTON_HEIGHT, BUTTON_WIDTH, GLYPH_SIZE_MAX, GLYPH_SIZE_MIN,
+    ICON, PAD,
+};
+use crate::fonts::{self, Fonts};
+use fltk::{
+    app,
+    button::Button,
+    draw,
+    enums::{Align, Color, Font, FrameType},
+    frame::Frame,
+    group::Flex,
+    image::SvgImage,
+    menu::Choice,
+    misc::Spinner,
+    prelude::*,
+    window::Window,
+};
+use std::cell::RefCell;
+use std::rc::Rc;
+
+pub struct Form {
+    form: Window,
+    view: Rc<RefCell<View>>,
+}
+
+impl Form {
+    pub fn new(
+        fonts: &Fonts,
+        preferred_font: usize,
+        c: Option<char>,
+    ) -> Self {
+        let mut form = make_form();
+        let mut vbox = Flex::default().size_of_parent().column();
+        vbox.set_margin(PAD);
+        vbox.set_pad(PAD);
+        let font_names = fonts::regular_fonts();
+        let (top_row, font_choice, size_spinner) =
+            make_top_row(&font_names, preferred_font);
+        vbox.set_size(&top_row, BUTTON_HEIGHT);
+        let mut canvas = Frame::default();
+        canvas.set_frame(FrameType::DownBox);
+        let mut metrics_frame =
+            Frame::default().with_align(Align::Inside | Align::Left);
+        metrics_frame.set_frame(FrameType::EngravedFrame);
+        vbox.set_size(&metrics_frame, BUTTON_HEIGHT);
+        let (button_row, mut close_button) = make_bottom_row();
+        vbox.set_size(&button_row, BUTTON_HEIGHT);
+        vbox.end();
+        form.end();
+        let view = Rc::from(RefCell::from(View {
+            fonts: fonts.clone(),
+            font_names,
+            font_choice,
+            size_spinner,
+            canvas,
+            metrics_frame,
+            c,
+        }));
+        add_event_handlers(&mut form, &mut close_button, &view);
+        form.show();
+        view.borrow_mut().refresh();
+        Self { form, view }
+    }
+
+    pub fn show(&mut self) {
+        self.form.show();
+    }
+
+    pub fn update(&mut self, c: Option<char>) {
+        if self.form.shown() {
+            let mut view = self.view.borrow_mut();
+            view.c = c;
+            view.refresh();
+        }
+    }
+}
+
+impl Drop for Form {
+    fn drop(&mut self) {
+        app::delete_widget(self.form.clone());
+    }
+}
+
+struct View {
+    fonts: Fonts,
+    font_names: Vec<(String, usize)>,
+    font_choice: Choice,
+    size_spinner: Spinner,
+    canvas: Frame,
+    metrics_frame: Frame,
+    c: Option<char>,
+}
+
+impl View {
+    fn font(&self) -> usize {
+        match self.font_names.get(self.font_choice.value() as usize) {
+            Some((_, i)) => *i,
+            None => 0,
+        }
+    }
+
+    fn size(&self) -> i32 {
+        self.size_spinner.value() as i32
+    }
+
+    fn refresh(&mut self) {
+        let font = self.font();
+        let size = self.size();
+        let c = self.c;
+        self.canvas.draw(move |canvas| draw_glyph(canvas, c, font, size));
+        if let Some(c) = c {
+            let metrics = Metrics::new(c, font, size);
+            let fallback = if self.fonts.font_covers(font, c as u32) {
+                ""
+            } else {
+                " • not in this font (fallback)"
+            };
+            self.metrics_frame.set_label(&format!(
+                "Ascent {} • Descent {} • Advance {} • Bounds {}×{}{}",
+                metrics.ascent,
+                metrics.descent,
+                metrics.advance,
+                metrics.bounds.2,
+                metrics.bounds.3,
+                fallback
+            ));
+        } else {
+            self.metrics_frame.set_label("");
+        }
+        self.canvas.redraw();
+    }
+
+    fn save(&self) {
+        let mut config = CONFIG.get().write().unwrap();
+        if let Some((name, _)) =
+            self.font_names.get(self.font_choice.value() as usize)
+        {
+            config.glyph_font = name.clone();
+        }
+        config.glyph_size = self.size();
+    }
+}
+
+struct Metrics {
+    ascent: i32,
+    descent: i32,
+    advance: i32,
+    bounds: (i32, i32, i32, i32), // dx, dy, width, height
+}
+
+impl Metrics {
+    // FLTK's measuring functions use the current font
+    fn new(c: char, font: usize, size: i32) -> Self {
+        let text = c.to_string();
+        draw::set_font(Font::by_index(font), size);
+        let descent = draw::descent();
+        Self {
+            ascent: draw::height() - descent,
+            descent,
+            advance: draw::width(&text).round() as i32,
+            bounds: draw::text_extents(&text),
+        }
+    }
+}
+
+fn draw_glyph(canvas: &mut Frame, c: Option<char>, font: usize, size: i32) {
+    let (x, y, width, height) =
+        (canvas.x(), canvas.y(), canvas.w(), canvas.h());
+    draw::push_clip(x, y, width, height);
+    draw::draw_rect_fill(x, y, width, height, Color::BackGround2);
+    if let Some(c) = c {
+        let metrics = Metrics::new(c, font, size);
+        let left = x + (width - metrics.advance) / 2;
+        let right = left + metrics.advance;
+        let baseline = y
+            + (height - (metrics.ascent + metrics.descent)) / 2
+            + metrics.ascent;
+        let top = baseline - metrics.ascent;
+        let bottom = baseline + metrics.descent;
+        draw::set_draw_color(Color::ForeGround);
+        draw::draw_text(&c.to_string(), left, baseline);
+        draw::set_line_style(draw::LineStyle::Solid, 1);
+        draw::set_draw_color(Color::Blue);
+        draw::draw_line(x, baseline, x + width, baseline);
+        draw::set_line_style(draw::LineStyle::Dash, 1);
+        draw::set_draw_color(Color::DarkGreen);
+        draw::draw_line(x, top, x + width, top);
+        draw::set_draw_color(Color::Red);
+        draw::draw_line(x, bottom, x + width, bottom);
+        draw::set_draw_color(Color::Magenta);
+        draw::draw_line(left, y, left, y + height);
+        draw::draw_line(right, y, right, y + height);
+        draw::set_line_style(draw::LineStyle::Dot, 1);
+        draw::set_draw_color(Color::DarkYellow);
+        let (dx, dy, bounds_width, bounds_height) = metrics.bounds;
+        draw::draw_rect(
+            left + dx,
+            baseline + dy,
+            bounds_width,
+            bounds_height,
+        );
+        draw::set_line_style(draw::LineStyle::Solid, 0);
+    }
+    draw::pop_clip();
+}
+
+fn make_form() -> Window {
+    let image = SvgImage::from_data(ICON).unwrap();
+    let mut form = Window::default()
+        .with_size(WIDTH, HEIGHT)
+        .with_label(&format!("Glyph Inspector — {APPNAME}"));
+    if let Some(window) = app::first_window() {
+        form.set_pos(window.x() + 50, window.y() + 100);
+    }
+    form.set_icon(Some(image));
+    form.make_resizable(true);
+    form
+}
+
+fn make_top_row(
+    font_names: &[(String, usize)],
+    preferred_font: usize,
+) -> (Flex, Choice, Spinner) {
+    let config = CONFIG.get().read().unwrap();
+    let mut row = Flex::default().row();
+    row.set_pad(PAD);
+    let mut font_label = Button::default()
+        .with_label("&Font")
+        .with_align(Align::Inside | Align::Right);
+    font_label.set_frame(FrameType::NoBox);
+    font_label.clear_visible_focus();
+    let mut font_choice = Choice::default();
+    let mut current = 0;
+    for (i, (name, font)) in font_names.iter().enumerate() {
+        font_choice.add_choice(&name.replace('/', "\\/"));
+        if (config.glyph_font.is_empty() && *font == preferred_font)
+            || *name == config.glyph_font
+        {
+            current = i as i32;
+        }
+    }
+    font_choice.set_value(current);
+    font_choice.set_tooltip("The font to show the character in");
+    let mut size_label = Button::default()
+        .with_label("&Size")
+        .with_align(Align::Inside | Align::Right);
+    size_label.set_frame(FrameType::NoBox);
+    size_label.clear_visible_focus();
+    let mut size_spinner = Spinner::default();
+    size_spinner.set_range(GLYPH_SIZE_MIN as f64, GLYPH_SIZE_MAX as f64);
+    size_spinner.set_step(8.0);
+    size_spinner.set_value(config.glyph_size as f64);
+    size_spinner.set_tooltip("The size to show the character at");
+    font_label.set_callback({
+        let mut font_choice = font_choice.clone();
+        move |_| {
+            font_choice.take_focus().unwrap_or_default();
+        }
+    });
+    size_label.set_callback({
+        let mut size_spinner = size_spinner.clone();
+        move |_| {
+            size_spinner.take_focus().unwrap_or_default();
+        }
+    });
+    row.set_size(&font_label, BUTTON_WIDTH / 2);
+    row.set_size(&size_label, BUTTON_WIDTH / 2);
+    row.set_size(&size_spinner, BUTTON_WIDTH);
+    row.end();
+    (row, font_choice, size_spinner)
+}
+
+fn make_bottom_row() -> (Flex, Button) {
+    let mut row = Flex::default().row();
+    Frame::default()
+        .with_label("Baseline (blue) • Ascent (green) • Descent (red) • Advance (magenta) • Bounds (yellow)")
+        .with_align(Align::Inside | Align::Left);
+    let close_button = Button::default().with_label("&Close");
+    row.set_size(&close_button, BUTTON_WIDTH);
+    row.end();
+    (row, close_button)
+}
+
+fn add_event_handlers(
+    form: &mut Window,
+    close_button: &mut Button,
+    view: &Rc<RefCell<View>>,
+) {
+    let mut borrowed = view.borrow_mut();
+    borrowed.font_choice.set_callback({
+        let view = Rc::clone(view);
+        move |_| {
+            let mut view = view.borrow_mut();
+            view.save();
+            view.refresh();
+        }
+    });
+    borrowed.size_spinner.set_callback({
+        let view = Rc::clone(view);
+        move |_| {
+            let mut view = view.borrow_mut();
+            view.save();
+            view.refresh();
+        }
+    });
+    close_button.set_callback({
+        let mut form = form.clone();
+        move |_| {
+            form.hide();
+        }
+    });
+}
+
+const WIDTH: i32 = 640;
+const HEIGHT: i32 = 560;
@@ -0,0 +1,369 @@
+// Copyright © 2021-22 Mark Summerfield. All rights reserved.
+// License: GPLv3
+
+use crate::fixed::HISTORY_MAX;
+use chrono::prelude::*;
+use std::cmp::Reverse;
+
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum HistoryOrder {
+    #[default]
+    Recency,
+    Frequency,
+}
+
+impl HistoryOrder {
+    pub const ALL: [HistoryOrder; 2] =
+        [HistoryOrder::Recency, HistoryOrder::Frequency];
+
+    // The name used in the configuration file
+    pub fn name(&self) -> &'static str {
+        match self {
+            HistoryOrder::Recency => "recency",
+            HistoryOrder::Frequency => "frequency",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<HistoryOrder> {
+        HistoryOrder::ALL.iter().find(|order| order.name() == name).copied()
+    }
+
+    // The name shown to the user
+    pub fn label(&self) -> &'static str {
+        match self {
+            HistoryOrder::Recency => "Most Recently Used",
+            HistoryOrder::Frequency => "Most Frequently Used",
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct HistoryItem {
+    pub c: char,
+    pub count: u32,
+    pub last_used: i64, // Seconds since the epoch; 0 for never
+    pub pinned: bool,
+}
+
+impl HistoryItem {
+    pub fn new(c: char) -> Self {
+        Self { c, count: 0, last_used: 0, pinned: false }
+    }
+
+    // Returns the local date and time of the last use (or "never")
+    pub fn last_used_text(&self) -> String {
+        match Local.timestamp_opt(self.last_used, 0).single() {
+            Some(when) if self.last_used > 0 => {
+                when.format("%Y-%m-%d %H:%M").to_string()
+            }
+            _ => "never".to_string(),
+        }
+    }
+}
+
+// The characters that have been added to the output editor, most recently
+// used first (after any pinned ones, which stay in the user's order), each
+// with its use count
+#[derive(Clone, Debug, Default)]
+pub struct History {
+    items: Vec<HistoryItem>,
+}
+
+impl History {
+    pub fn items(&self) -> &[HistoryItem] {
+        &self.items
+    }
+
+    fn contains(&self, c: char) -> bool {
+        self.items.iter().any(|item| item.c == c)
+    }
+
+    fn find(&self, c: char) -> Option<&HistoryItem> {
+        self.items.iter().find(|item| item.c == c)
+    }
+
+    // Adds an item that hasn't been used to the end, e.g., when reading the
+    // configuration or adding defaults; duplicates are ignored
+    pub fn push(&mut self, item: HistoryItem) {
+        if !self.contains(item.c) && self.items.len() < HISTORY_MAX {
+            self.items.push(item);
+        }
+    }
+
+    // Records a use of the character moving it to the front (unless it is
+    // pinned); the least recently used character is dropped if the history
+    // is full
+    pub fn add(&mut self, c: char) {
+        let pinned =
+            self.items.iter().take_while(|item| item.pinned).count();
+        let i = self.items.iter().position(|item| item.c == c);
+        let mut item = match i {
+            Some(i) if self.items[i].pinned => {
+                let item = &mut self.items[i];
+                item.count = item.count.saturating_add(1);
+                item.last_used = Local::now().timestamp();
+                return;
+            }
+            Some(i) => self.items.remove(i),
+            None => HistoryItem::new(c),
+        };
+        item.count = item.count.saturating_add(1);
+        item.last_used = Local::now().timestamp();
+        self.items.insert(pinned, item);
+        self.items.truncate(HISTORY_MAX);
+    }
+
+    // Shared characters keep the higher use count and the later use and are
+    // pinned if either is; the unpinned characters end up most recently
+    // used first
+    pub fn merge(&mut self, other: &History) {
+        for item in other.items.iter() {
+            match self.items.iter_mut().find(|old| old.c == item.c) {
+                Some(old) => {
+                    old.count = old.count.max(item.count);
+                    old.last_used = old.last_used.max(item.last_used);
+                    old.pinned |= item.pinned;
+                }
+                None => self.items.push(*item),
+            }
+        }
+        self.sort_by_recency();
+    }
+
+    // Merges in the changes another instance made to the history that
+    // this one started from (base): what either added is kept, what either
+    // removed (and this one hasn't used since) is dropped, and the uses
+    // made by both are counted
+    pub fn merge_changes(&mut self, base: &History, theirs: &History) {
+        self.items.retain(|item| match base.find(item.c) {
+            Some(old) => {
+                theirs.contains(item.c) || item.last_used > old.last_used
+            }
+            None => true, // Added by this instance
+        });
+        let mut reorder = false;
+        for item in theirs.items.iter() {
+            let old = base.find(item.c);
+            match self.items.iter_mut().find(|ours| ours.c == item.c) {
+                Some(ours) => {
+                    let count = old.map_or(0, |old| old.count);
+                    ours.count = ours
+                        .count
+                        .saturating_add(item.count.saturating_sub(count));
+                    if item.last_used > ours.last_used {
+                        ours.last_used = item.last_used;
+                        reorder = true;
+                    }
+                    if old.map(|old| old.pinned) == Some(ours.pinned)
+                        && ours.pinned != item.pinned
+                    {
+                        ours.pinned = item.pinned; // Only they changed it
+                        reorder = true;
+                    }
+                }
+                None if old.is_none() => {
+                    self.items.push(*item);
+                    reorder = true;
+                }
+                None => {} // Removed by this instance
+            }
+        }
+        // Only reorder if needed so as not to lose the user's order
+        if reorder {
+            self.sort_by_recency();
+        }
+    }
+
+    // Stable so the pinned ones keep their order
+    fn sort_by_recency(&mut self) {
+        self.items.sort_by_key(|item| {
+            (
+                !item.pinned,
+                Reverse(if item.pinned { 0 } else { item.last_used }),
+            )
+        });
+        self.items.truncate(HISTORY_MAX);
+    }
+
+    pub fn remove(&mut self, c: char) {
+        self.items.retain(|item| item.c != c);
+    }
+
+    // Returns the items in the given order with the pinned ones first
+    // (ties are most recent first)
+    pub fn sorted(&self, order: HistoryOrder) -> Vec<HistoryItem> {
+        let mut items = self.items.clone();
+        if order == HistoryOrder::Frequency {
+            // Stable so the pinned ones keep their order
+            items.sort_by_key(|item| {
+                (
+                    !item.pinned,
+                    Reverse(if item.pinned { 0 } else { item.count }),
+                )
+            });
+        }
+        items
+    }
+
+    // Each item is saved as HEX:COUNT:LAST_USED (with :1 appended if it is
+    // pinned) in the history's order
+    pub fn to_config(&self) -> String {
+        self.items
+            .iter()
+            .map(|item| {
+                format!(
+                    "{:X}:{}:{}{}",
+                    item.c as u32,
+                    item.count,
+                    item.last_used,
+                    if item.pinned { ":1" } else { "" }
+                )
+            })
+            .collect::<Vec<String>>()
+            .join(",")
+    }
+
+    pub fn from_config(text: &str) -> Self {
+        let mut history = History::default();
+        for field in text.split(',') {
+            let mut parts = field.split(':');
+            let c = parts
+                .next()
+                .and_then(|hex| u32::from_str_radix(hex.trim(), 16).ok())
+                .and_then(char::from_u32);
+            if let Some(c) = c {
+                let count = parts
+                    .next()
+                    .and_then(|count| count.parse().ok())
+                    .unwrap_or_default();
+                let last_used = parts
+                    .next()
+                    .and_then(|last_used| last_used.parse().ok())
+                    .unwrap_or_default();
+                let pinned = parts.next() == Some("1");
+                history.push(HistoryItem { c, count, last_used, pinned });
+            }
+        }
+        history
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn item(
+        c: char,
+        count: u32,
+        last_used: i64,
+        pinned: bool,
+    ) -> HistoryItem {
+        HistoryItem { c, count, last_used, pinned }
+    }
+
+    fn history(items: &[HistoryItem]) -> History {
+        let mut history = History::default();
+        for item in items {
+            history.push(*item);
+        }
+        history
+    }
+
+    fn chars(history: &History) -> String {
+        history.items().iter().map(|item| item.c).collect()
+    }
+
+    #[test]
+    fn config_round_trip() {
+        let items = [item('→', 3, 100, true), item('é', 1, 50, false)];
+        let text = history(&items).to_config();
+        assert_eq!(text, "2192:3:100:1,E9:1:50");
+        assert_eq!(History::from_config(&text).items(), items);
+    }
+
+    #[test]
+    fn from_config_skips_bad_fields() {
+        let history = History::from_config("zz:1:2,E9,,41:x:y,E9:5:5");
+        assert_eq!(
+            history.items(),
+            [item('é', 0, 0, false), item('A', 0, 0, false)]
+        );
+    }
+
+    #[test]
+    fn add_moves_to_the_front_after_the_pinned() {
+        let mut history = history(&[
+            item('→', 1, 1, true),
+            item('a', 1, 1, false),
+            item('b', 1, 1, false),
+        ]);
+        history.add('b');
+        assert_eq!(chars(&history), "→ba");
+        assert_eq!(history.items()[1].count, 2);
+        history.add('→');
+        assert_eq!(chars(&history), "→ba");
+        assert_eq!(history.items()[0].count, 2);
+        history.add('c');
+        assert_eq!(chars(&history), "→cba");
+        assert_eq!(history.items()[1].count, 1);
+        assert!(history.items()[1].last_used > 0);
+    }
+
+    #[test]
+    fn sorted_by_frequency_keeps_the_pinned_first() {
+        let history = history(&[
+            item('p', 1, 1, true),
+            item('a', 1, 9, false),
+            item('b', 5, 1, false),
+            item('q', 0, 0, true),
+        ]);
+        let sorted = |order| {
+            history
+                .sorted(order)
+                .iter()
+                .map(|item| item.c)
+                .collect::<String>()
+        };
+        assert_eq!(sorted(HistoryOrder::Recency), "pabq");
+        assert_eq!(sorted(HistoryOrder::Frequency), "pqba");
+    }
+
+    #[test]
+    fn merge_keeps_the_larger_count_and_latest_use() {
+        let theirs = history(&[
+            item('a', 3, 5, false),
+            item('b', 1, 20, false),
+            item('p', 0, 0, true),
+        ]);
+        let mut history = history(&[item('a', 1, 10, false)]);
+        history.merge(&theirs);
+        assert_eq!(chars(&history), "pba");
+        assert_eq!(history.items()[2], item('a', 3, 10, false));
+    }
+
+    #[test]
+    fn merge_changes_keeps_what_either_did() {
+        let base =
+            history(&[item('a', 1, 10, false), item('b', 1, 5, false)]);
+        let theirs = history(&[
+            item('a', 1, 10, false),
+            item('b', 1, 5, false),
+            item('c', 1, 40, false),
+        ]);
+        let mut history = history(&[item('a', 2, 30, false)]);
+        history.merge_changes(&base, &theirs);
+        assert_eq!(chars(&history), "ca");
+        assert_eq!(history.items()[1], item('a', 2, 30, false));
+    }
+
+    #[test]
+    fn merge_changes_counts_the_uses_made_by_both() {
+        let base =
+            history(&[item('a', 1, 10, false), item('b', 1, 5, false)]);
+        let theirs = history(&[item('a', 3, 20, true)]);
+        let mut history = base.clone();
+        history.items[0].count = 2;
+        history.merge_changes(&base, &theirs);
+        assert_eq!(history.items(), [item('a', 4, 20, true)]);
+    }
+}
@@ -0,0 +1,243 @@
+// Copyright © 2021-22 Mark Summerfield. All rights reserved.
+// License: GPLv3
+
+use super::CONFIG;
+use crate::fixed::{
+    Action, APPNAME, BUTTON_HEIGHT, BUTTON_WIDTH, ICON, PAD,
+};
+use fltk::{
+    app,
+    app::Sender,
+    browser::HoldBrowser,
+    button::Button,
+    enums::{Align, CallbackTrigger, Font, FrameType},
+    frame::Frame,
+    group::Flex,
+    image::SvgImage,
+    input::Input,
+    prelude::*,
+    window::Window,
+};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+use thousands::Separable;
+
+pub struct Form {
+    form: Window,
+    view: Rc<RefCell<View>>,
+}
+
+impl Form {
+    pub fn new(
+        sender: Sender<Action>,
+        font: usize,
+        names: HashMap<u32, String>,
+    ) -> Self {
+        let mut form = make_form();
+        let mut vbox = Flex::default().size_of_parent().column();
+        vbox.set_margin(PAD);
+        vbox.set_pad(PAD);
+        let (top_row, mut filter_input) = make_top_row();
+        vbox.set_size(&top_row, BUTTON_HEIGHT);
+        let mut browser = HoldBrowser::default();
+        browser.set_column_char('\t');
+        browser.set_column_widths(&COLUMN_WIDTHS);
+        browser.set_text_font(Font::by_index(font));
+        browser.set_tooltip(
+            "Double-click a character to add it to the output editor",
+        );
+        let mut row = Flex::default().row();
+        row.set_pad(PAD);
+        let summary_frame =
+            Frame::default().with_align(Align::Inside | Align::Left);
+        let mut add_button = Button::default().with_label("&Add");
+        add_button
+            .set_tooltip("Add the selected character to the output editor");
+        let mut remove_button = Button::default().with_label("&Remove");
+        remove_button
+            .set_tooltip("Remove the selected character from the history");
+        let mut close_button = Button::default().with_label("&Close");
+        row.set_size(&add_button, BUTTON_WIDTH);
+        row.set_size(&remove_button, BUTTON_WIDTH);
+        row.set_size(&close_button, BUTTON_WIDTH);
+        row.end();
+        vbox.set_size(&row, BUTTON_HEIGHT);
+        vbox.end();
+        form.end();
+        let view = Rc::from(RefCell::from(View {
+            names,
+            chars: vec![],
+            filter_input: filter_input.clone(),
+            browser: browser.clone(),
+            summary_frame,
+        }));
+        filter_input.set_callback({
+            let view = Rc::clone(&view);
+            move |_| {
+                view.borrow_mut().populate();
+            }
+        });
+        browser.set_callback({
+            let view = Rc::clone(&view);
+            move |_| {
+                if app::event_clicks() {
+                    if let Some(c) = view.borrow().selected() {
+                        sender.send(Action::AddChar(c));
+                    }
+                }
+            }
+        });
+        add_button.set_callback({
+            let view = Rc::clone(&view);
+            move |_| {
+                if let Some(c) = view.borrow().selected() {
+                    sender.send(Action::AddChar(c));
+                }
+            }
+        });
+        remove_button.set_callback({
+            let view = Rc::clone(&view);
+            move |_| {
+                if let Some(c) = view.borrow().selected() {
+                    sender.send(Action::RemoveFromHistory(c));
+                }
+            }
+        });
+        close_button.set_callback({
+            let mut form = form.clone();
+            move |_| {
+                form.hide();
+            }
+        });
+        form.show();
+        view.borrow_mut().populate();
+        Self { form, view }
+    }
+
+    pub fn show(&mut self) {
+        self.form.show();
+    }
+
+    pub fn update(&mut self, names: HashMap<u32, String>) {
+        if self.form.shown() {
+            let mut view = self.view.borrow_mut();
+            view.names = names;
+            view.populate();
+        }
+    }
+}
+
+impl Drop for Form {
+    fn drop(&mut self) {
+        app::delete_widget(self.form.clone());
+    }
+}
+
+struct View {
+    names: HashMap<u32, String>,
+    // The character for each browser line after the title
+    chars: Vec<char>,
+    filter_input: Input,
+    browser: HoldBrowser,
+    summary_frame: Frame,
+}
+
+impl View {
+    fn selected(&self) -> Option<char> {
+        let line = self.browser.value();
+        if line > 1 {
+            self.chars.get(line as usize - 2).copied()
+        } else {
+            None
+        }
+    }
+
+    // Shows the history items whose character, code point, or name match
+    // every word in the filter
+    fn populate(&mut self) {
+        let selected = self.selected();
+        let words = self
+            .filter_input
+            .value()
+            .split_whitespace()
+            .map(|word| word.to_uppercase())
+            .collect::<Vec<String>>();
+        let config = CONFIG.get().read().unwrap();
+        let items = config.history.sorted(config.history_order);
+        self.chars.clear();
+        self.browser.clear();
+        self.browser.add("@bChar\t@bU+HHHH\t@bUses\t@bLast Used\t@bName");
+        for item in items.iter() {
+            let hex = format!("{:04X}", item.c as u32);
+            let name = self
+                .names
+                .get(&(item.c as u32))
+                .map(|name| name.as_str())
+                .unwrap_or_default();
+            if words.iter().all(|word| {
+                name.contains(word.as_str())
+                    || word.trim_start_matches("U+") == hex
+                    || *word == item.c.to_uppercase().to_string()
+            }) {
+                self.browser.add(&format!(
+                    "@.{}\t{hex}\t{}\t{}\t{}",
+                    item.c,
+                    item.count.separate_with_commas(),
+                    item.last_used_text(),
+                    name.to_lowercase()
+                ));
+                self.chars.push(item.c);
+                if Some(item.c) == selected {
+                    self.browser.select(self.browser.size());
+                }
+            }
+        }
+        let s = if items.len() == 1 { "" } else { "s" };
+        self.summary_frame.set_label(&format!(
+            "Showing {} of {} character{s} ({})",
+            self.chars.len().separate_with_commas(),
+            items.len().separate_with_commas(),
+            config.history_order.label().to_lowercase()
+        ));
+    }
+}
+
+fn make_form() -> Window {
+    let image = SvgImage::from_data(ICON).unwrap();
+    let mut form = Window::default()
+        .with_size(WIDTH, HEIGHT)
+        .with_label(&format!("History — {APPNAME}"));
+    if let Some(window) = app::first_window() {
+        form.set_pos(window.x() + 50, window.y() + 100);
+    }
+    form.set_icon(Some(image));
+    form.make_resizable(true);
+    form
+}
+
+fn make_top_row() -> (Flex, Input) {
+    let mut row = Flex::default().row();
+    row.set_pad(PAD);
+    let mut filter_label = Button::default()
+        .with_label("&Filter")
+        .with_align(Align::Inside | Align::Right);
+    filter_label.set_frame(FrameType::NoBox);
+    filter_label.clear_visible_focus();
+    let mut filter_input = Input::default();
+    filter_input.set_trigger(CallbackTrigger::Changed);
+    filter_input.set_tooltip("Only show the characters whose names have all these words (or that are the character or have the U+HHHH code point)");
+    filter_label.set_callback({
+        let mut filter_input = filter_input.clone();
+        move |_| {
+            filter_input.take_focus().unwrap_or_default();
+        }
+    });
+    row.set_size(&filter_label, BUTTON_WIDTH / 2);
+    row.end();
+    (row, filter_input)
+}
+
+const WIDTH: i32 = 640;
+const HEIGHT: i32 = 480;
+const COLUMN_WIDTHS: [i32; 4] = [50, 70, 60, 140];
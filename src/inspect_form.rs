@@ -0,0 +1,234 @@
+// Copyright © 2021-22 Mark Summerfield. All rights reserved.
+// License: GPLv3
+
+use crate::escape;
+use crate::fixed::{
+    Action, APPNAME, BUTTON_HEIGHT, BUTTON_WIDTH, ICON, PAD,
+};
+use fltk::{
+    app,
+    app::Sender,
+    browser::HoldBrowser,
+    button::Button,
+    enums::{Align, Font},
+    frame::Frame,
+    group::Flex,
+    image::SvgImage,
+    prelude::*,
+    window::Window,
+};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+use unicode_normalization::char::is_combining_mark;
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+pub struct Form {
+    form: Window,
+    browser: HoldBrowser,
+    summary_frame: Frame,
+    // The character for each browser line (None for grapheme rows)
+    chars: Rc<RefCell<Vec<Option<char>>>>,
+}
+
+impl Form {
+    pub fn new(
+        sender: Sender<Action>,
+        font: usize,
+        text: &str,
+        names: &HashMap<u32, String>,
+    ) -> Self {
+        let mut form = make_form();
+        let mut vbox = Flex::default().size_of_parent().column();
+        vbox.set_margin(PAD);
+        vbox.set_pad(PAD);
+        let mut browser = HoldBrowser::default();
+        browser.set_column_char('\t');
+        browser.set_column_widths(&COLUMN_WIDTHS);
+        browser.set_text_font(Font::by_index(font));
+        browser.set_tooltip("Click a code point's row to select its character in the search results table");
+        let mut row = Flex::default().row();
+        let summary_frame =
+            Frame::default().with_align(Align::Inside | Align::Left);
+        let mut close_button = Button::default().with_label("&Close");
+        row.set_size(&close_button, BUTTON_WIDTH);
+        row.end();
+        vbox.set_size(&row, BUTTON_HEIGHT);
+        vbox.end();
+        form.end();
+        let chars = Rc::from(RefCell::from(vec![]));
+        browser.set_callback({
+            let chars = Rc::clone(&chars);
+            move |browser| {
+                let line = browser.value();
+                if line > 0 {
+                    if let Some(Some(c)) =
+                        chars.borrow().get(line as usize - 1)
+                    {
+                        sender.send(Action::SelectChar(*c));
+                    }
+                }
+            }
+        });
+        close_button.set_callback({
+            let mut form = form.clone();
+            move |_| {
+                form.hide();
+            }
+        });
+        form.show();
+        let mut inspect_form = Self { form, browser, summary_frame, chars };
+        inspect_form.populate(text, names);
+        inspect_form
+    }
+
+    pub fn show(&mut self) {
+        self.form.show();
+    }
+
+    pub fn shown(&self) -> bool {
+        self.form.shown()
+    }
+
+    pub fn update(&mut self, text: &str, names: &HashMap<u32, String>) {
+        if self.form.shown() {
+            self.populate(text, names);
+        }
+    }
+
+    fn populate(&mut self, text: &str, names: &HashMap<u32, String>) {
+        let mut chars = self.chars.borrow_mut();
+        chars.clear();
+        self.browser.clear();
+        self.browser.add("@bCharacter\t@bU+HHHH\t@bUTF-8\t@bWidth\t@bName");
+        chars.push(None);
+        let mut clusters = 0;
+        let mut flagged = 0;
+        for (i, grapheme) in text.graphemes(true).enumerate() {
+            clusters += 1;
+            let count = grapheme.chars().count();
+            let s = if count == 1 { "" } else { "s" };
+            self.browser.add(&format!(
+                "@b@.{}\t\t@b{}\t@b{}\t@bGrapheme #{} ({count} code point{s})",
+                shown(grapheme),
+                grapheme.len(),
+                grapheme.width(),
+                i + 1,
+            ));
+            chars.push(None);
+            for c in grapheme.chars() {
+                let flag = flag_for(c);
+                if !flag.is_empty() {
+                    flagged += 1;
+                }
+                let name = match names.get(&(c as u32)) {
+                    Some(name) => name.to_string(),
+                    None => fallback_name(c).to_string(),
+                };
+                // Format codes only work at the start of a column so a
+                // flagged row's whole name is colored
+                let name = if flag.is_empty() {
+                    name
+                } else {
+                    format!("@C1{name} [{flag}]")
+                };
+                self.browser.add(&format!(
+                    "@.    {}\tU+{:04X}\t{}\t{}\t{name}",
+                    shown(&c.to_string()),
+                    c as u32,
+                    utf8_bytes(c),
+                    match c.width() {
+                        Some(width) => width.to_string(),
+                        None => "–".to_string(),
+                    },
+                ));
+                chars.push(Some(c));
+            }
+        }
+        let s = if clusters == 1 { "" } else { "s" };
+        self.summary_frame.set_label(&format!(
+            "{clusters} grapheme{s} • {} code points • {} UTF-8 bytes • width {}{}",
+            text.chars().count(),
+            text.len(),
+            text.width(),
+            if flagged > 0 {
+                format!(" • {flagged} invisible or bidi control")
+            } else {
+                String::new()
+            },
+        ));
+    }
+}
+
+impl Drop for Form {
+    fn drop(&mut self) {
+        app::delete_widget(self.form.clone());
+    }
+}
+
+fn make_form() -> Window {
+    let image = SvgImage::from_data(ICON).unwrap();
+    let mut form = Window::default()
+        .with_size(WIDTH, HEIGHT)
+        .with_label(&format!("Inspect Output — {APPNAME}"));
+    if let Some(window) = app::first_window() {
+        form.set_pos(window.x() + 50, window.y() + 100);
+    }
+    form.set_icon(Some(image));
+    form.make_resizable(true);
+    form
+}
+
+// Returns the text with invisible and control characters made visible;
+// it is shown after @. so '@' needs no escaping
+fn shown(text: &str) -> String {
+    let mut shown = String::new();
+    for c in text.chars() {
+        if c.is_control() || !flag_for(c).is_empty() {
+            shown.push('·');
+        } else if is_combining_mark(c) && text.chars().count() == 1 {
+            shown.push('◌');
+            shown.push(c);
+        } else {
+            shown.push(c);
+        }
+    }
+    shown
+}
+
+fn utf8_bytes(c: char) -> String {
+    let mut buffer = [0; 4];
+    c.encode_utf8(&mut buffer)
+        .bytes()
+        .map(|b| format!("{b:02X}"))
+        .collect::<Vec<String>>()
+        .join(" ")
+}
+
+fn flag_for(c: char) -> &'static str {
+    match c {
+        c if escape::is_bidi_control(c) => "bidi control",
+        c if escape::is_invisible(c) => "invisible",
+        c if c.is_control() && c != '\n' && c != '\t' => "invisible",
+        _ => "",
+    }
+}
+
+// The character data doesn't have combining marks, controls, or spaces
+fn fallback_name(c: char) -> &'static str {
+    match c {
+        '\n' => "LINE FEED",
+        '\t' => "CHARACTER TABULATION",
+        ' ' => "SPACE",
+        '\u{00A0}' => "NO-BREAK SPACE",
+        c if c.is_control() => "(control character)",
+        c if is_combining_mark(c) => "(combining mark)",
+        c if c.is_whitespace() => "(space character)",
+        _ => "(no name available)",
+    }
+}
+
+const WIDTH: i32 = 720;
+const HEIGHT: i32 = 480;
+const COLUMN_WIDTHS: [i32; 4] = [100, 90, 120, 60];
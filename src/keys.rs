@@ -0,0 +1,624 @@
+// Copyright © 2021-22 Mark Summerfield. All rights reserved.
+// License: GPLv3
+
+use crate::fixed::{Action, Popup};
+use fltk::enums::{EventState, Key};
+
+// Where a binding's shortcuts work: the window's work wherever the focus
+// is (unless the focused widget uses the key itself); the output editor's
+// only work when it has the focus
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Scope {
+    Window,
+    OutputEditor,
+}
+
+struct Binding {
+    name: &'static str, // The key used in the configuration file
+    action: Action,
+    scope: Scope,
+    defaults: &'static str,
+    help: &'static str, // The HTML shown in the help's shortcuts table
+}
+
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct KeyCombo {
+    key: i32,
+    ctrl: bool,
+    alt: bool,
+    shift: bool,
+    meta: bool,
+}
+
+impl KeyCombo {
+    // Parses, e.g., F2, Alt+A, or Ctrl+Shift+Z; plain and Shift+ keys are
+    // refused (except for function keys and Help) since they're needed for
+    // typing
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut combo = KeyCombo::default();
+        let parts = text.split('+').map(|part| part.trim());
+        let parts = parts.collect::<Vec<&str>>();
+        let (name, modifiers) = match parts.split_last() {
+            Some((name, modifiers)) if !name.is_empty() => {
+                (name, modifiers)
+            }
+            _ => return Err(format!("“{text}” has no key")),
+        };
+        for modifier in modifiers.iter() {
+            match modifier.to_lowercase().as_str() {
+                "ctrl" => combo.ctrl = true,
+                "alt" => combo.alt = true,
+                "shift" => combo.shift = true,
+                "meta" | "cmd" => combo.meta = true,
+                _ => return Err(format!("“{modifier}” isn't a modifier")),
+            }
+        }
+        combo.key = key_for_name(name)
+            .ok_or_else(|| format!("“{name}” isn't a key"))?
+            .bits();
+        let key = Key::from_i32(combo.key);
+        if !(combo.ctrl || combo.alt || combo.meta)
+            && !Key::is_fn_key(key)
+            && key != Key::Help
+        {
+            return Err(format!(
+                "“{text}” needs Ctrl, Alt, or Meta since it isn't a function key or Help"
+            ));
+        }
+        Ok(combo)
+    }
+
+    pub fn matches(&self, key: Key, state: EventState) -> bool {
+        self.key == key.bits()
+            && self.ctrl == state.contains(EventState::Ctrl)
+            && self.alt == state.contains(EventState::Alt)
+            && self.shift == state.contains(EventState::Shift)
+            && self.meta == state.contains(EventState::Meta)
+    }
+
+    // Returns the combination as it is written in the configuration file
+    // and shown to the user, e.g., Ctrl+Shift+Z
+    pub fn text(&self) -> String {
+        let mut text = String::new();
+        for (on, modifier) in [
+            (self.ctrl, "Ctrl+"),
+            (self.alt, "Alt+"),
+            (self.shift, "Shift+"),
+            (self.meta, "Meta+"),
+        ] {
+            if on {
+                text.push_str(modifier);
+            }
+        }
+        text.push_str(&name_for_key(Key::from_i32(self.key)));
+        text
+    }
+
+    // Returns the letter or digit of an Alt+letter or Alt+digit combination
+    fn alt_char(&self) -> Option<char> {
+        if self.alt && !self.ctrl && !self.shift && !self.meta {
+            char::from_u32(self.key as u32)
+                .filter(|c| c.is_ascii_alphanumeric())
+        } else {
+            None
+        }
+    }
+}
+
+// The key combinations for each binding in BINDINGS order
+#[derive(Clone, Debug)]
+pub struct KeyBindings {
+    combos: Vec<Vec<KeyCombo>>,
+}
+
+impl Default for KeyBindings {
+    fn default() -> Self {
+        let combos = BINDINGS
+            .iter()
+            .map(|binding| {
+                parse_combos(binding.defaults).unwrap_or_default()
+            })
+            .collect();
+        Self { combos }
+    }
+}
+
+impl KeyBindings {
+    // Invalid shortcuts are replaced by the binding's defaults and
+    // shortcuts already used by an earlier binding are dropped; both (and
+    // unknown names) are noted in problems
+    pub fn from_config(
+        properties: &ini::Properties,
+        section: &str,
+        problems: &mut Vec<String>,
+    ) -> Self {
+        let defaults = KeyBindings::default();
+        let mut used = RESERVED
+            .iter()
+            .filter_map(|text| KeyCombo::parse(text).ok())
+            .map(|combo| (combo, "editing"))
+            .collect::<Vec<(KeyCombo, &str)>>();
+        let mut combos = vec![];
+        for (binding, default) in BINDINGS.iter().zip(defaults.combos) {
+            let wanted = match properties.get(binding.name) {
+                Some(value) => match parse_combos(value) {
+                    Ok(wanted) => wanted,
+                    Err(err) => {
+                        problems.push(format!(
+                            "{section} {}: {err}; using {}",
+                            binding.name,
+                            combos_text(&default, "none")
+                        ));
+                        default
+                    }
+                },
+                None => default,
+            };
+            let mut unused = vec![];
+            for combo in wanted {
+                match used.iter().find(|(old, _)| *old == combo) {
+                    Some((_, name)) => problems.push(format!(
+                        "{section} {}: {} is already used for {name}",
+                        binding.name,
+                        combo.text()
+                    )),
+                    None => {
+                        used.push((combo, binding.name));
+                        unused.push(combo);
+                    }
+                }
+            }
+            combos.push(unused);
+        }
+        for (name, _) in properties.iter() {
+            if !BINDINGS.iter().any(|binding| binding.name == name) {
+                problems.push(format!(
+                    "{section} {name}: isn't a key binding's name"
+                ));
+            }
+        }
+        Self { combos }
+    }
+
+    // Returns each binding's name and its key combinations as they are
+    // written in the configuration file (empty if it is unbound)
+    pub fn to_config(&self) -> Vec<(&'static str, String)> {
+        BINDINGS
+            .iter()
+            .zip(self.combos.iter())
+            .map(|(binding, combos)| {
+                (binding.name, combos_text(combos, ""))
+            })
+            .collect()
+    }
+
+    pub fn action_for(
+        &self,
+        scope: Scope,
+        key: Key,
+        state: EventState,
+    ) -> Option<Action> {
+        BINDINGS
+            .iter()
+            .zip(self.combos.iter())
+            .find(|(binding, combos)| {
+                binding.scope == scope
+                    && combos.iter().any(|combo| combo.matches(key, state))
+            })
+            .map(|(binding, _)| binding.action)
+    }
+
+    // Returns the label with an & before the first occurrence of the
+    // letter of the binding's Alt+letter combination (if it has one and
+    // the letter is in the label) so that it is underlined
+    pub fn label(&self, name: &str, label: &str) -> String {
+        if let Some(c) =
+            self.combos_for(name).iter().find_map(|combo| combo.alt_char())
+        {
+            if let Some((i, _)) = label
+                .char_indices()
+                .find(|(_, l)| l.eq_ignore_ascii_case(&c))
+            {
+                return format!("{}&{}", &label[..i], &label[i..]);
+            }
+        }
+        label.to_string()
+    }
+
+    // Returns the tooltip with the binding's key combinations appended
+    pub fn tooltip(&self, name: &str, tooltip: &str) -> String {
+        let combos = self.combos_for(name);
+        if combos.is_empty() {
+            tooltip.to_string()
+        } else {
+            format!("{tooltip} ({})", combos_text(combos, ""))
+        }
+    }
+
+    // Returns the help's shortcuts table rows for the bound bindings
+    pub fn help_rows(&self) -> String {
+        let mut rows = String::new();
+        for (binding, combos) in BINDINGS.iter().zip(self.combos.iter()) {
+            if !combos.is_empty() {
+                let keys = combos
+                    .iter()
+                    .map(|combo| format!("<b>{}</b>", combo.text()))
+                    .collect::<Vec<String>>()
+                    .join(" or ");
+                rows.push_str(&format!(
+                    "<tr><td>{keys}</td><td>{}</td></tr>\n",
+                    binding.help
+                ));
+            }
+        }
+        rows
+    }
+
+    fn combos_for(&self, name: &str) -> &[KeyCombo] {
+        match BINDINGS.iter().position(|binding| binding.name == name) {
+            Some(i) => self.combos[i].as_slice(),
+            None => &[],
+        }
+    }
+}
+
+fn parse_combos(text: &str) -> Result<Vec<KeyCombo>, String> {
+    let mut combos = vec![];
+    for part in text.split(',').filter(|part| !part.trim().is_empty()) {
+        let combo = KeyCombo::parse(part)?;
+        if !combos.contains(&combo) {
+            combos.push(combo);
+        }
+    }
+    Ok(combos)
+}
+
+fn combos_text(combos: &[KeyCombo], none: &str) -> String {
+    if combos.is_empty() {
+        none.to_string()
+    } else {
+        combos
+            .iter()
+            .map(|combo| combo.text())
+            .collect::<Vec<String>>()
+            .join(", ")
+    }
+}
+
+fn key_for_name(name: &str) -> Option<Key> {
+    if let Some(n) = name
+        .strip_prefix('F')
+        .or_else(|| name.strip_prefix('f'))
+        .and_then(|n| n.parse::<i32>().ok())
+    {
+        return if (1..=12).contains(&n) {
+            Some(Key::fn_key(n))
+        } else {
+            None
+        };
+    }
+    if let Some((_, key)) =
+        named_keys().iter().find(|(n, _)| n.eq_ignore_ascii_case(name))
+    {
+        return Some(*key);
+    }
+    let mut chars = name.chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), None) if c.is_ascii_graphic() => {
+            Some(Key::from_char(c.to_ascii_lowercase()))
+        }
+        _ => None,
+    }
+}
+
+fn name_for_key(key: Key) -> String {
+    if Key::is_fn_key(key) {
+        return format!("F{}", key.bits() - Key::fn_key(0).bits());
+    }
+    if let Some((name, _)) = named_keys().iter().find(|(_, k)| *k == key) {
+        return name.to_string();
+    }
+    match char::from_u32(key.bits() as u32) {
+        Some(c) => c.to_ascii_uppercase().to_string(),
+        None => format!("{:X}", key.bits()),
+    }
+}
+
+fn named_keys() -> [(&'static str, Key); 12] {
+    [
+        ("Space", Key::from_char(' ')),
+        ("Insert", Key::Insert),
+        ("Delete", Key::Delete),
+        ("Home", Key::Home),
+        ("End", Key::End),
+        ("PageUp", Key::PageUp),
+        ("PageDown", Key::PageDown),
+        ("Left", Key::Left),
+        ("Right", Key::Right),
+        ("Up", Key::Up),
+        ("Down", Key::Down),
+        ("Help", Key::Help),
+    ]
+}
+
+// Used by the editors and the search results table
+static RESERVED: [&str; 4] = ["Ctrl+A", "Ctrl+C", "Ctrl+V", "Ctrl+X"];
+
+static BINDINGS: [Binding; 20] = [
+    Binding {
+        name: "help",
+        action: Action::Help,
+        scope: Scope::Window,
+        defaults: "F1, Help",
+        help: "Show this Help window",
+    },
+    Binding {
+        name: "recent-searches",
+        action: Action::PopupSearches,
+        scope: Scope::Window,
+        defaults: "F2",
+        help: "Pop up the Recent Searches—and replace the current search \
+               with the chosen one",
+    },
+    Binding {
+        name: "search-results",
+        action: Action::FocusToSearchResults,
+        scope: Scope::Window,
+        defaults: "F3",
+        help: "Move the keyboard focus to the Search Results Table",
+    },
+    Binding {
+        name: "accents",
+        action: Action::AccentPicker,
+        scope: Scope::Window,
+        defaults: "F4",
+        help: "Pop up the accented variants of the letter before the \
+               cursor (e.g., <i>e</i> → <i>é è ê ë ē …</i>), most common \
+               first; press a number to choose one. In the Output Editor \
+               the chosen variant replaces the letter; in the Search box \
+               it is added to the Output Editor",
+    },
+    Binding {
+        name: "add",
+        action: Action::AddSelected,
+        scope: Scope::Window,
+        defaults: "Alt+A",
+        help: "Add the characters from the selected rows in the Search \
+               Results Table to the Output Editor in table order; <i>see \
+               also</i> <b>Double-Click</b>",
+    },
+    Binding {
+        name: "code-point",
+        action: Action::ToggleCodePoint,
+        scope: Scope::OutputEditor,
+        defaults: "Alt+X",
+        help: "In the Output Editor, convert the hex digits (or \
+               <i>U+HHHH</i>) before the cursor into the character (e.g., \
+               <i>e9</i> → <i>é</i>), or the character before the cursor \
+               into <i>U+HHHH</i>; surrogates, noncharacters, and code \
+               points that aren't in the character data are refused",
+    },
+    Binding {
+        name: "undo",
+        action: Action::Undo,
+        scope: Scope::OutputEditor,
+        defaults: "Ctrl+Z",
+        help: "Undo the last change to the Output Editor (when it has the \
+               keyboard focus); <i>see also</i> the Tools menu",
+    },
+    Binding {
+        name: "redo",
+        action: Action::Redo,
+        scope: Scope::OutputEditor,
+        defaults: "Ctrl+Y, Ctrl+Shift+Z",
+        help: "Redo the last undone change to the Output Editor (when it \
+               has the keyboard focus)",
+    },
+    Binding {
+        name: "about",
+        action: Action::About,
+        scope: Scope::Window,
+        defaults: "Alt+B",
+        help: "Show the About box",
+    },
+    Binding {
+        name: "history",
+        action: Action::PopupMenu(Popup::History),
+        scope: Scope::Window,
+        defaults: "Alt+H",
+        help: "Pop up the History of added characters—and add the chosen \
+               one to the Output Editor (or choose <b>All History…</b> to \
+               show the History panel); <i>see below</i>",
+    },
+    Binding {
+        name: "snippets",
+        action: Action::PopupMenu(Popup::Snippets),
+        scope: Scope::Window,
+        defaults: "Alt+N",
+        help: "Pop up the Snippets menu—and add the chosen snippet to the \
+               Output Editor, or save the Output Editor's selected text (or \
+               all its text) as a snippet; <i>see below</i>",
+    },
+    Binding {
+        name: "favorites",
+        action: Action::PopupMenu(Popup::Favorites),
+        scope: Scope::Window,
+        defaults: "Alt+V",
+        help: "Pop up the Favorites menu—and add the chosen character to \
+               the Output Editor; <i>see below</i>",
+    },
+    Binding {
+        name: "copy",
+        action: Action::Copy,
+        scope: Scope::Window,
+        defaults: "Alt+C",
+        help: "Copy the text from the Output Editor to the clipboard (as it \
+               is, or in the last Copy As format if the option is checked)",
+    },
+    Binding {
+        name: "clear",
+        action: Action::Clear,
+        scope: Scope::Window,
+        defaults: "Alt+L",
+        help: "Clear the Output Editor",
+    },
+    Binding {
+        name: "options",
+        action: Action::Options,
+        scope: Scope::Window,
+        defaults: "Alt+O",
+        help: "Show the Options dialog; <i>see below</i>",
+    },
+    Binding {
+        name: "copy-as",
+        action: Action::PopupMenu(Popup::CopyAs),
+        scope: Scope::Window,
+        defaults: "Alt+Y",
+        help: "Pop up the Copy As menu to copy the text from the Output \
+               Editor to the clipboard as Rust escapes, HTML entities, CSS \
+               escapes, URL encoding, code points, or LaTeX",
+    },
+    Binding {
+        name: "quit",
+        action: Action::Quit,
+        scope: Scope::Window,
+        defaults: "Alt+Q",
+        help: "Quit the application",
+    },
+    Binding {
+        name: "transform",
+        action: Action::PopupMenu(Popup::Transform),
+        scope: Scope::Window,
+        defaults: "Alt+R",
+        help: "Pop up the Transform menu; <i>see below</i>",
+    },
+    Binding {
+        name: "search",
+        action: Action::FocusToSearch,
+        scope: Scope::Window,
+        defaults: "Alt+S",
+        help: "Move the keyboard focus to the Search box",
+    },
+    Binding {
+        name: "tools",
+        action: Action::PopupMenu(Popup::Tools),
+        scope: Scope::Window,
+        defaults: "Alt+T",
+        help: "Pop up the Tools menu; <i>see below</i>",
+    },
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn text(combo: &str) -> Result<String, String> {
+        KeyCombo::parse(combo).map(|combo| combo.text())
+    }
+
+    #[test]
+    fn parse_combos_with_modifiers() {
+        assert_eq!(text("Ctrl+Shift+Z"), Ok("Ctrl+Shift+Z".to_string()));
+        assert_eq!(text(" alt + a "), Ok("Alt+A".to_string()));
+        assert_eq!(text("Shift+Ctrl+z"), Ok("Ctrl+Shift+Z".to_string()));
+        assert_eq!(text("cmd+space"), Ok("Meta+Space".to_string()));
+        assert_eq!(text("Alt+PageDown"), Ok("Alt+PageDown".to_string()));
+        assert_eq!(text("Ctrl+1"), Ok("Ctrl+1".to_string()));
+    }
+
+    #[test]
+    fn parse_function_keys_and_help_without_modifiers() {
+        assert_eq!(text("F1"), Ok("F1".to_string()));
+        assert_eq!(text("f12"), Ok("F12".to_string()));
+        assert_eq!(text("Shift+F3"), Ok("Shift+F3".to_string()));
+        assert_eq!(text("help"), Ok("Help".to_string()));
+        assert_eq!(text("Shift+Help"), Ok("Shift+Help".to_string()));
+    }
+
+    #[test]
+    fn parse_refuses_bad_combos() {
+        assert_eq!(text(""), Err("“” has no key".to_string()));
+        assert_eq!(text("Ctrl+"), Err("“Ctrl+” has no key".to_string()));
+        assert_eq!(
+            text("Hyper+A"),
+            Err("“Hyper” isn't a modifier".to_string())
+        );
+        assert_eq!(text("Ctrl+F13"), Err("“F13” isn't a key".to_string()));
+        assert_eq!(
+            text("Ctrl+Enter"),
+            Err("“Enter” isn't a key".to_string())
+        );
+        assert!(text("A").is_err());
+        assert!(text("Shift+A").is_err());
+        assert!(text("Space").is_err());
+    }
+
+    #[test]
+    fn matches_only_the_exact_modifiers() {
+        let combo = KeyCombo::parse("Ctrl+Z").unwrap();
+        assert!(combo.matches(Key::from_char('z'), EventState::Ctrl));
+        assert!(!combo.matches(
+            Key::from_char('z'),
+            EventState::Ctrl | EventState::Shift
+        ));
+        assert!(!combo.matches(Key::from_char('y'), EventState::Ctrl));
+    }
+
+    #[test]
+    fn parse_combos_drops_duplicates() {
+        let combos = parse_combos("Alt+A, alt+a,, Ctrl+A").unwrap();
+        assert_eq!(combos_text(&combos, ""), "Alt+A, Ctrl+A");
+        assert!(parse_combos("Alt+A, A").is_err());
+    }
+
+    #[test]
+    fn default_labels_and_tooltips() {
+        let keys = KeyBindings::default();
+        assert_eq!(keys.label("add", "Add"), "&Add");
+        assert_eq!(keys.label("history", "Show History"), "S&how History");
+        assert_eq!(keys.label("help", "Help"), "Help");
+        assert_eq!(keys.label("unknown", "Help"), "Help");
+        assert_eq!(
+            keys.tooltip("help", "Show help"),
+            "Show help (F1, Help)"
+        );
+        assert_eq!(
+            keys.tooltip("redo", "Redo"),
+            "Redo (Ctrl+Y, Ctrl+Shift+Z)"
+        );
+        assert_eq!(keys.tooltip("unknown", "Tip"), "Tip");
+    }
+
+    #[test]
+    fn from_config_reports_problems() {
+        let ini = ini::Ini::load_from_str(
+            "[Keys]\nadd = Alt+Q\nquit = Alt+Q, Ctrl+Q\nundo = Ctrl+C\n\
+             copy = Hyper+C\nbogus = F5\n",
+        )
+        .unwrap();
+        let mut problems = vec![];
+        let keys = KeyBindings::from_config(
+            ini.section(Some("Keys")).unwrap(),
+            "Keys",
+            &mut problems,
+        );
+        assert_eq!(
+            problems,
+            [
+                "Keys undo: Ctrl+C is already used for editing",
+                "Keys copy: “Hyper” isn't a modifier; using Alt+C",
+                "Keys quit: Alt+Q is already used for add",
+                "Keys bogus: isn't a key binding's name",
+            ]
+        );
+        let config = keys.to_config();
+        let combos = |name: &str| {
+            config.iter().find(|(n, _)| *n == name).unwrap().1.as_str()
+        };
+        assert_eq!(combos("add"), "Alt+Q");
+        assert_eq!(combos("quit"), "Ctrl+Q");
+        assert_eq!(combos("undo"), "");
+        assert_eq!(combos("copy"), "Alt+C");
+        assert_eq!(combos("help"), "F1, Help");
+    }
+}
@@ -0,0 +1,220 @@
+// Copyright © 2021-22 Mark Summerfield. All rights reserved.
+// License: GPLv3
+
+use crate::fixed::{APPNAME, BUTTON_HEIGHT, BUTTON_WIDTH, PAD};
+use fltk::{
+    browser::HoldBrowser, button::Button, dialog, frame::Frame,
+    group::Flex, prelude::*,
+};
+use std::cell::RefCell;
+use std::rc::Rc;
+
+#[derive(Clone, Debug)]
+pub struct Entry {
+    pub text: String,
+    pub pinned: bool,
+    // The entry's index in the list the editor started with or None if it
+    // was edited or reset
+    pub original: Option<usize>,
+}
+
+impl Entry {
+    pub fn new(text: &str) -> Self {
+        Self { text: text.to_string(), pinned: false, original: None }
+    }
+}
+
+// An editor for a list of strings (e.g., the searches or the history)
+// whose pinned entries are always kept first
+pub struct ListEditor {
+    view: Rc<RefCell<View>>,
+}
+
+impl ListEditor {
+    // Adds the editor to the current group; show returns the text to show
+    // for an entry and edit returns the normalized text of an edited entry
+    // or None if it isn't acceptable
+    pub fn new(
+        entries: Vec<Entry>,
+        defaults: Vec<Entry>,
+        show: fn(&str) -> String,
+        edit: fn(&str) -> Option<String>,
+    ) -> Self {
+        let mut row = Flex::default().row();
+        row.set_pad(PAD);
+        let browser = HoldBrowser::default();
+        let mut column = Flex::default().column();
+        column.set_pad(PAD);
+        let mut edit_button = Button::default().with_label("&Edit…");
+        let mut delete_button = Button::default().with_label("&Delete");
+        let mut up_button = Button::default().with_label("Move &Up");
+        let mut down_button = Button::default().with_label("Move Do&wn");
+        let mut pin_button = Button::default().with_label("&Pin/Unpin");
+        pin_button.set_tooltip(
+            "Pinned entries are kept first and are never moved or dropped",
+        );
+        Frame::default();
+        let mut reset_button =
+            Button::default().with_label("&Reset to Defaults");
+        for button in [
+            &edit_button,
+            &delete_button,
+            &up_button,
+            &down_button,
+            &pin_button,
+            &reset_button,
+        ] {
+            column.set_size(button, BUTTON_HEIGHT);
+        }
+        column.end();
+        row.set_size(&column, BUTTON_WIDTH + PAD * 8);
+        row.end();
+        let view = Rc::from(RefCell::from(View {
+            entries,
+            defaults,
+            show,
+            browser,
+        }));
+        view.borrow_mut().populate(1);
+        edit_button.set_callback({
+            let view = Rc::clone(&view);
+            move |_| {
+                view.borrow_mut().edit(edit);
+            }
+        });
+        delete_button.set_callback({
+            let view = Rc::clone(&view);
+            move |_| {
+                view.borrow_mut().delete();
+            }
+        });
+        up_button.set_callback({
+            let view = Rc::clone(&view);
+            move |_| {
+                view.borrow_mut().move_by(-1);
+            }
+        });
+        down_button.set_callback({
+            let view = Rc::clone(&view);
+            move |_| {
+                view.borrow_mut().move_by(1);
+            }
+        });
+        pin_button.set_callback({
+            let view = Rc::clone(&view);
+            move |_| {
+                view.borrow_mut().toggle_pin();
+            }
+        });
+        reset_button.set_callback({
+            let view = Rc::clone(&view);
+            move |_| {
+                let mut view = view.borrow_mut();
+                view.entries = view.defaults.clone();
+                view.populate(1);
+            }
+        });
+        Self { view }
+    }
+
+    pub fn entries(&self) -> Vec<Entry> {
+        self.view.borrow().entries.clone()
+    }
+}
+
+struct View {
+    entries: Vec<Entry>,
+    defaults: Vec<Entry>,
+    show: fn(&str) -> String,
+    browser: HoldBrowser,
+}
+
+impl View {
+    fn selected(&self) -> Option<usize> {
+        let line = self.browser.value();
+        if line > 0 && line as usize <= self.entries.len() {
+            Some(line as usize - 1)
+        } else {
+            None
+        }
+    }
+
+    fn pinned(&self) -> usize {
+        self.entries.iter().take_while(|entry| entry.pinned).count()
+    }
+
+    fn populate(&mut self, line: i32) {
+        self.browser.clear();
+        for entry in self.entries.iter() {
+            let text = (self.show)(&entry.text);
+            self.browser.add(&if entry.pinned {
+                format!("@b@.{text}  (pinned)")
+            } else {
+                format!("@.{text}")
+            });
+        }
+        if !self.entries.is_empty() {
+            self.browser.select(line.clamp(1, self.entries.len() as i32));
+        }
+    }
+
+    fn edit(&mut self, edit: fn(&str) -> Option<String>) {
+        if let Some(i) = self.selected() {
+            dialog::message_title(&format!("Edit — {APPNAME}"));
+            let text = match dialog::input_default(
+                "Edit",
+                &self.entries[i].text,
+            ) {
+                Some(text) => text,
+                None => return,
+            };
+            match edit(&text) {
+                Some(text) if text == self.entries[i].text => {}
+                Some(text)
+                    if !self
+                        .entries
+                        .iter()
+                        .any(|entry| entry.text == text) =>
+                {
+                    self.entries[i].text = text;
+                    self.entries[i].original = None;
+                    self.populate(i as i32 + 1);
+                }
+                _ => dialog::beep(dialog::BeepType::Error),
+            }
+        }
+    }
+
+    fn delete(&mut self) {
+        if let Some(i) = self.selected() {
+            self.entries.remove(i);
+            self.populate(i as i32 + 1);
+        }
+    }
+
+    // Entries can only be moved among the pinned or the unpinned entries
+    fn move_by(&mut self, delta: i32) {
+        if let Some(i) = self.selected() {
+            let j = i as i32 + delta;
+            if j >= 0
+                && (j as usize) < self.entries.len()
+                && self.entries[j as usize].pinned == self.entries[i].pinned
+            {
+                self.entries.swap(i, j as usize);
+                self.populate(j + 1);
+            }
+        }
+    }
+
+    // Pinning moves the entry to the end of the pinned ones; unpinning
+    // moves it to the start of the unpinned ones
+    fn toggle_pin(&mut self) {
+        if let Some(i) = self.selected() {
+            let mut entry = self.entries.remove(i);
+            entry.pinned = !entry.pinned;
+            let j = self.pinned();
+            self.entries.insert(j, entry);
+            self.populate(j as i32 + 1);
+        }
+    }
+}
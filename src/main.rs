@@ -2,20 +2,46 @@
 // License: GPLv3
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
+mod accents;
 mod application;
+mod blocks;
+mod compare_form;
+mod composer_form;
 mod config;
+mod escape;
+mod exchange;
+mod exchange_form;
+mod fancy;
+mod fancy_form;
+mod favorites_form;
 mod fixed;
+mod fonts;
+mod glyph_form;
+mod history;
+mod history_form;
 mod html_form;
+mod inspect_form;
+mod keys;
+mod list_editor;
 mod main_window;
 mod options_form;
+mod theme;
+mod transform;
+mod undo;
 mod util;
 
 use crate::application::Application;
 use crate::fixed::APPNAME;
-use config::Config;
+use config::{Config, CONFIG_ENV_VAR};
 use fltk::dialog;
 use state::Storage;
-use std::{panic, sync};
+use std::{
+    env,
+    ffi::{OsStr, OsString},
+    panic,
+    path::PathBuf,
+    process, sync,
+};
 
 pub static CONFIG: Storage<sync::RwLock<Config>> = Storage::new();
 
@@ -27,7 +53,65 @@ fn main() {
         let y = util::y() - 100;
         dialog::message(x, y, &err.to_string());
     }));
-    CONFIG.set(sync::RwLock::new(Config::new()));
+    CONFIG.set(sync::RwLock::new(Config::new(config_filename_arg())));
     let mut app = Application::new();
     app.run();
 }
+
+// Returns the filename given by --config FILENAME (or --config=FILENAME)
+// if there is one; the arguments are kept as OS strings so that both forms
+// accept any filename the OS does
+fn config_filename_arg() -> Option<PathBuf> {
+    let mut filename = None;
+    let mut args = env::args_os().skip(1);
+    while let Some(arg) = args.next() {
+        if arg == "--config" {
+            match args.next() {
+                Some(value) if !value.is_empty() => {
+                    filename = Some(PathBuf::from(value))
+                }
+                _ => usage("--config requires a filename"),
+            }
+        } else if let Some(value) = strip_config_prefix(&arg) {
+            if value.is_empty() {
+                usage("--config requires a filename");
+            }
+            filename = Some(PathBuf::from(value));
+        } else {
+            usage(&format!(
+                "unrecognized argument: {}",
+                arg.to_string_lossy()
+            ));
+        }
+    }
+    filename
+}
+
+#[cfg(unix)]
+fn strip_config_prefix(arg: &OsStr) -> Option<OsString> {
+    use std::os::unix::ffi::OsStrExt;
+    arg.as_bytes()
+        .strip_prefix(CONFIG_PREFIX.as_bytes())
+        .map(|value| OsStr::from_bytes(value).to_os_string())
+}
+
+#[cfg(windows)]
+fn strip_config_prefix(arg: &OsStr) -> Option<OsString> {
+    use std::os::windows::ffi::{OsStrExt, OsStringExt};
+    let arg = arg.encode_wide().collect::<Vec<u16>>();
+    let prefix = CONFIG_PREFIX.encode_utf16().collect::<Vec<u16>>();
+    arg.strip_prefix(prefix.as_slice()).map(OsString::from_wide)
+}
+
+// There may be no console (e.g., on Windows) so this uses a dialog
+fn usage(message: &str) -> ! {
+    dialog::message_title(&format!("Usage — {APPNAME}"));
+    dialog::alert_default(&format!(
+        "{message}\n\nusage: {} [--config FILENAME]\n\nThe configuration file is the one given by --config or else by the {CONFIG_ENV_VAR} environment variable or else the {}.ini next to the executable (if there is one) or else the usual one.",
+        APPNAME.to_lowercase(),
+        APPNAME.to_lowercase()
+    ));
+    process::exit(2);
+}
+
+static CONFIG_PREFIX: &str = "--config=";
@@ -2,58 +2,102 @@
 // License: GPLv3
 
 use super::CONFIG;
+use crate::escape::{self, Format};
+use crate::fancy::Style;
 use crate::fixed::{
-    Action, APPNAME, BUTTON_HEIGHT, BUTTON_WIDTH, ICON, MENU_CHARS, PAD,
-    ROW_HEIGHT, WINDOW_HEIGHT_MIN, WINDOW_WIDTH_MIN,
+    Action, APPNAME, BUTTON_HEIGHT, BUTTON_WIDTH, ICON, MENU_CHARS,
+    OUTPUT_HEIGHT, PAD, ROW_HEIGHT, SNIPPET_PREVIEW_MAX, WINDOW_HEIGHT_MIN,
+    WINDOW_WIDTH_MIN,
 };
+use crate::keys::{KeyBindings, Scope};
+use crate::transform::Transform;
+use crate::undo::{EditHistory, EditState};
 use crate::util;
 use fltk::{
     app,
     app::Sender,
-    browser::HoldBrowser,
+    browser::{HoldBrowser, MultiBrowser},
     button::Button,
-    enums::{Align, Event, FrameType, Key, Shortcut},
+    enums::{Align, Event, EventState, FrameType, Key, Shortcut},
     frame::Frame,
     group::Flex,
     image::SvgImage,
-    input::Input,
     menu::{MenuButton, MenuFlag},
     misc::InputChoice,
     prelude::*,
+    text::{TextBuffer, TextEditor, WrapMode},
     window::Window,
 };
+use std::cell::RefCell;
+use std::rc::Rc;
 
 pub struct Widgets {
     pub main_window: Window,
     pub find_combo: InputChoice,
     pub history_menu_button: MenuButton,
-    pub browser: HoldBrowser,
-    pub copy_input: Input,
+    pub favorites_menu_button: MenuButton,
+    pub snippets_menu_button: MenuButton,
+    pub copy_as_menu_button: MenuButton,
+    pub transform_menu_button: MenuButton,
+    pub tools_menu_button: MenuButton,
+    pub browser: MultiBrowser,
+    pub output_editor: TextEditor,
+    pub nfc_frame: Frame,
     pub preview_frame: Frame,
+    pub completion_browser: HoldBrowser,
 }
 
 pub fn make(sender: Sender<Action>) -> Widgets {
     Window::set_default_xclass(APPNAME);
     let (main_window, width) = make_main_window();
+    let keys = {
+        let config = CONFIG.get().read().unwrap();
+        config.keys.clone()
+    };
     let mut vbox = Flex::default().column().size_of_parent();
     vbox.set_margin(PAD);
-    let (find_combo, history_menu_button, top_row) =
-        add_top_row(sender, width);
+    let (find_combo, menu_buttons, top_row) =
+        add_top_row(sender, width, &keys);
+    let [history_menu_button, favorites_menu_button, snippets_menu_button] =
+        menu_buttons;
     vbox.set_size(&top_row, ROW_HEIGHT);
-    let (browser, copy_input, preview_frame) =
-        add_middle_row(sender, width);
+    let (browser, preview_frame, menu_buttons) =
+        add_middle_row(sender, width, &keys);
+    let [copy_as_menu_button, tools_menu_button] = menu_buttons;
+    let (output_editor, nfc_frame, transform_menu_button, bottom_row) =
+        add_bottom_row(sender, width, &keys);
+    vbox.set_size(&bottom_row, OUTPUT_HEIGHT);
     vbox.end();
+    let completion_browser = make_completion_browser(sender);
     main_window.end();
     Widgets {
         main_window,
         find_combo,
         history_menu_button,
+        favorites_menu_button,
+        snippets_menu_button,
+        copy_as_menu_button,
+        transform_menu_button,
+        tools_menu_button,
         browser,
-        copy_input,
+        output_editor,
+        nfc_frame,
         preview_frame,
+        completion_browser,
     }
 }
 
+// Made after the layout so that it floats over it when the output
+// editor's completions are shown
+fn make_completion_browser(sender: Sender<Action>) -> HoldBrowser {
+    let mut completion_browser = HoldBrowser::default();
+    completion_browser.set_frame(FrameType::BorderBox);
+    completion_browser.visible_focus(false);
+    completion_browser.emit(sender, Action::Complete);
+    completion_browser.hide();
+    completion_browser
+}
+
 fn make_main_window() -> (Window, i32) {
     let icon = SvgImage::from_data(ICON).unwrap();
     let (x, y, width, height) = get_config_window_rect();
@@ -72,13 +116,14 @@ fn make_main_window() -> (Window, i32) {
 fn add_top_row(
     sender: Sender<Action>,
     width: i32,
-) -> (InputChoice, MenuButton, Flex) {
+    keys: &KeyBindings,
+) -> (InputChoice, [MenuButton; 3], Flex) {
     let mut row = Flex::default().row().with_size(width, ROW_HEIGHT);
     row.set_margin(PAD);
     let mut find_label = Button::default();
     find_label.set_frame(FrameType::NoBox);
     find_label.visible_focus(false);
-    find_label.set_label("&Search:");
+    find_label.set_label(&keys.label("search", "Search:"));
     find_label.set_align(Align::Inside | Align::Right);
     let mut find_combo = InputChoice::default();
     initialize_find_combo(&mut find_combo, sender);
@@ -91,14 +136,35 @@ fn add_top_row(
     });
     row.set_size(&find_label, BUTTON_WIDTH);
     let mut history_menu_button =
-        MenuButton::default().with_label("&History");
-    history_menu_button.set_tooltip(
+        MenuButton::default().with_label(&keys.label("history", "History"));
+    history_menu_button.set_tooltip(&keys.tooltip(
+        "history",
         "Add a previously added character to the output editor",
-    );
+    ));
     populate_history_menu_button(&mut history_menu_button, sender);
     row.set_size(&history_menu_button, BUTTON_WIDTH);
+    let mut favorites_menu_button = MenuButton::default()
+        .with_label(&keys.label("favorites", "Favorites"));
+    favorites_menu_button.set_tooltip(&keys.tooltip(
+        "favorites",
+        "Add a character from a favorites group to the output editor",
+    ));
+    populate_favorites_menu_button(&mut favorites_menu_button, sender);
+    row.set_size(&favorites_menu_button, BUTTON_WIDTH);
+    let mut snippets_menu_button = MenuButton::default()
+        .with_label(&keys.label("snippets", "Snippets"));
+    snippets_menu_button.set_tooltip(&keys.tooltip(
+        "snippets",
+        "Add a saved snippet of text to the output editor or save one",
+    ));
+    populate_snippets_menu_button(&mut snippets_menu_button, sender);
+    row.set_size(&snippets_menu_button, BUTTON_WIDTH);
     row.end();
-    (find_combo, history_menu_button, row)
+    (
+        find_combo,
+        [history_menu_button, favorites_menu_button, snippets_menu_button],
+        row,
+    )
 }
 
 fn initialize_find_combo(
@@ -125,16 +191,28 @@ fn initialize_find_combo(
 fn add_middle_row(
     sender: Sender<Action>,
     width: i32,
-) -> (HoldBrowser, Input, Frame) {
+    keys: &KeyBindings,
+) -> (MultiBrowser, Frame, [MenuButton; 2]) {
     let mut row = Flex::default().row().with_size(width, ROW_HEIGHT);
     row.set_margin(PAD);
-    let mut browser = HoldBrowser::default();
+    let mut browser = MultiBrowser::default();
     browser.set_column_char('\t');
     browser.handle(move |browser, event| {
         if browser.has_focus() {
+            if event == Event::KeyDown
+                && app::event_state().contains(EventState::Ctrl)
+                && app::event_key() == Key::from_char('a')
+            {
+                sender.send(Action::SelectAll);
+                return true;
+            }
             if event == Event::KeyUp || event == Event::Released {
                 sender.send(Action::UpdatePreview);
             }
+            if event == Event::Released && app::event_button() == 3 {
+                sender.send(Action::ResultsMenu);
+                return true;
+            }
             if app::event_is_click()
                 && !app::event_inside_widget(&browser.scrollbar())
                 && app::event_button() == 1
@@ -146,38 +224,42 @@ fn add_middle_row(
         }
         false
     });
-    let (copy_input, preview_frame, column) = add_right_column(sender);
+    let (preview_frame, menu_buttons, column) =
+        add_right_column(sender, keys);
     row.set_size(&column, BUTTON_WIDTH);
     row.end();
-    (browser, copy_input, preview_frame)
+    (browser, preview_frame, menu_buttons)
 }
 
-fn add_right_column(sender: Sender<Action>) -> (Input, Frame, Flex) {
+fn add_right_column(
+    sender: Sender<Action>,
+    keys: &KeyBindings,
+) -> (Frame, [MenuButton; 2], Flex) {
     let mut column = Flex::default().column();
     add_button(
-        "Add the selected character from the table to the output editor",
-        "&Add",
-        Action::AddFromTable,
+        keys,
+        "add",
+        "Add the selected characters from the table to the output editor",
+        "Add",
+        Action::AddSelected,
         sender,
         &mut column,
     );
-    let copy_text = {
-        let config = CONFIG.get().read().unwrap();
-        config.copy_text.clone()
-    };
-    let mut copy_input = Input::default();
-    copy_input.set_value(&copy_text);
-    copy_input.set_tooltip("The output editor: chosen characters are added here and the text here gets copied to the clipboard");
     add_button(
+        keys,
+        "copy",
         "Copy the output editor's text to the clipboard",
-        "&Copy",
+        "Copy",
         Action::Copy,
         sender,
         &mut column,
     );
+    let copy_as_menu_button = add_copy_as_menu_button(sender, keys);
     add_button(
+        keys,
+        "clear",
         "Clear the output editor's",
-        "C&lear",
+        "Clear",
         Action::Clear,
         sender,
         &mut column,
@@ -185,14 +267,19 @@ fn add_right_column(sender: Sender<Action>) -> (Input, Frame, Flex) {
     let mut preview_frame = Frame::default();
     let size = preview_frame.label_size();
     preview_frame.set_label_size(size * 3);
+    let tools_menu_button = add_tools_menu_button(sender, keys);
     add_button(
+        keys,
+        "options",
         "Pop up the Options dialog",
-        "&Options…",
+        "Options…",
         Action::Options,
         sender,
         &mut column,
     );
     add_button(
+        keys,
+        "help",
         "Show the Help window",
         "Help",
         Action::Help,
@@ -200,35 +287,110 @@ fn add_right_column(sender: Sender<Action>) -> (Input, Frame, Flex) {
         &mut column,
     );
     add_button(
+        keys,
+        "about",
         "Pop up the About box",
-        "A&bout",
+        "About",
         Action::About,
         sender,
         &mut column,
     );
     Frame::default().with_size(PAD, PAD);
     add_button(
+        keys,
+        "quit",
         "Quit the application",
-        "&Quit",
+        "Quit",
         Action::Quit,
         sender,
         &mut column,
     );
-    column.set_size(&copy_input, BUTTON_HEIGHT);
     column.set_size(&preview_frame, BUTTON_HEIGHT * 3);
+    column.set_size(&copy_as_menu_button, BUTTON_HEIGHT);
+    column.set_size(&tools_menu_button, BUTTON_HEIGHT);
     column.end();
-    (copy_input, preview_frame, column)
+    (preview_frame, [copy_as_menu_button, tools_menu_button], column)
 }
 
+fn add_bottom_row(
+    sender: Sender<Action>,
+    width: i32,
+    keys: &KeyBindings,
+) -> (TextEditor, Frame, MenuButton, Flex) {
+    let mut row = Flex::default().row().with_size(width, OUTPUT_HEIGHT);
+    row.set_margin(PAD);
+    let copy_text = {
+        let config = CONFIG.get().read().unwrap();
+        config.copy_text.clone()
+    };
+    let mut buffer = TextBuffer::default();
+    buffer.set_text(&copy_text);
+    buffer.add_modify_callback(move |_, _, _, _, _| {
+        sender.send(Action::OutputChanged);
+    });
+    let mut output_editor = TextEditor::default();
+    output_editor.set_buffer(buffer);
+    output_editor.wrap_mode(WrapMode::AtBounds, 0);
+    output_editor.set_tooltip("The output editor: chosen characters are added here and the text here gets copied to the clipboard");
+    let mut column = Flex::default().column();
+    let mut transform_menu_button = MenuButton::default()
+        .with_label(&keys.label("transform", "Transform"));
+    transform_menu_button.set_tooltip(&keys.tooltip(
+        "transform",
+        "Normalize or change the case of the output editor's selected text (or all its text if none is selected)",
+    ));
+    transform_menu_button.visible_focus(false);
+    for (i, transform) in Transform::ALL.iter().enumerate() {
+        transform_menu_button.add_emit(
+            transform.label(),
+            Shortcut::None,
+            if i == 3 || i == Transform::ALL.len() - 1 {
+                MenuFlag::MenuDivider
+            } else {
+                MenuFlag::Normal
+            },
+            sender,
+            Action::Transform(*transform),
+        );
+    }
+    for style in Style::ALL {
+        transform_menu_button.add_emit(
+            &format!("&Fancy Text/{}", style.label()),
+            Shortcut::None,
+            MenuFlag::Normal,
+            sender,
+            Action::Fancy(style),
+        );
+    }
+    transform_menu_button.add_emit(
+        "Fancy Text Previe&w…",
+        Shortcut::None,
+        MenuFlag::Normal,
+        sender,
+        Action::FancyPreview,
+    );
+    let mut nfc_frame = Frame::default();
+    nfc_frame.set_frame(FrameType::EngravedFrame);
+    column.set_size(&transform_menu_button, BUTTON_HEIGHT);
+    column.set_size(&nfc_frame, BUTTON_HEIGHT);
+    column.end();
+    row.set_size(&column, BUTTON_WIDTH);
+    row.end();
+    (output_editor, nfc_frame, transform_menu_button, row)
+}
+
+// The name is the button's key binding's name
 fn add_button(
+    keys: &KeyBindings,
+    name: &str,
     tooltip: &str,
     label: &str,
     action: Action,
     sender: Sender<Action>,
     column: &mut Flex,
 ) {
-    let mut button = Button::default().with_label(label);
-    button.set_tooltip(tooltip);
+    let mut button = Button::default().with_label(&keys.label(name, label));
+    button.set_tooltip(&keys.tooltip(name, tooltip));
     button.visible_focus(false);
     button.set_callback(move |_| {
         sender.send(action);
@@ -236,6 +398,104 @@ fn add_button(
     column.set_size(&button, BUTTON_HEIGHT);
 }
 
+fn add_copy_as_menu_button(
+    sender: Sender<Action>,
+    keys: &KeyBindings,
+) -> MenuButton {
+    let mut copy_as_menu_button =
+        MenuButton::default().with_label(&keys.label("copy-as", "Copy As"));
+    copy_as_menu_button.set_tooltip(&keys.tooltip(
+        "copy-as",
+        "Copy the output editor's text to the clipboard in a chosen format",
+    ));
+    copy_as_menu_button.visible_focus(false);
+    for format in Format::ALL {
+        copy_as_menu_button.add_emit(
+            format.label(),
+            Shortcut::None,
+            MenuFlag::Normal,
+            sender,
+            Action::CopyAs(format),
+        );
+    }
+    copy_as_menu_button
+}
+
+fn add_tools_menu_button(
+    sender: Sender<Action>,
+    keys: &KeyBindings,
+) -> MenuButton {
+    let mut tools_menu_button =
+        MenuButton::default().with_label(&keys.label("tools", "Tools"));
+    tools_menu_button
+        .set_tooltip(&keys.tooltip("tools", "Pop up the Tools menu"));
+    tools_menu_button.visible_focus(false);
+    tools_menu_button.add_emit(
+        "&Undo Output Editor Change",
+        Shortcut::None,
+        MenuFlag::Normal,
+        sender,
+        Action::Undo,
+    );
+    tools_menu_button.add_emit(
+        "&Redo Output Editor Change",
+        Shortcut::None,
+        MenuFlag::MenuDivider,
+        sender,
+        Action::Redo,
+    );
+    tools_menu_button.add_emit(
+        "U&nescape Output Editor's Text",
+        Shortcut::None,
+        MenuFlag::MenuDivider,
+        sender,
+        Action::Unescape,
+    );
+    tools_menu_button.add_emit(
+        "&Inspect Output Editor's Text…",
+        Shortcut::None,
+        MenuFlag::MenuDivider,
+        sender,
+        Action::Inspect,
+    );
+    tools_menu_button.add_emit(
+        "Co&mposer…",
+        Shortcut::None,
+        MenuFlag::Normal,
+        sender,
+        Action::Composer,
+    );
+    tools_menu_button.add_emit(
+        "&Compare Fonts…",
+        Shortcut::None,
+        MenuFlag::Normal,
+        sender,
+        Action::Compare,
+    );
+    tools_menu_button.add_emit(
+        "&Glyph Inspector…",
+        Shortcut::None,
+        MenuFlag::MenuDivider,
+        sender,
+        Action::GlyphInspector,
+    );
+    tools_menu_button.add_emit(
+        "&Export Saved Data…",
+        Shortcut::None,
+        MenuFlag::Normal,
+        sender,
+        Action::Export,
+    );
+    tools_menu_button.add_emit(
+        "Im&port Saved Data…",
+        Shortcut::None,
+        MenuFlag::Normal,
+        sender,
+        Action::Import,
+    );
+    tools_menu_button
+}
+
 pub(crate) fn populate_history_menu_button(
     history_menu_button: &mut MenuButton,
     sender: Sender<Action>,
@@ -244,16 +504,116 @@ pub(crate) fn populate_history_menu_button(
     let config = CONFIG.get().read().unwrap();
     let size = config.history_size;
     let base = if (10..=26).contains(&size) { 9 } else { 0 };
-    for (i, c) in config.history.iter().enumerate() {
-        if i == size {
-            break;
-        }
+    let items = config.history.sorted(config.history_order);
+    let shown = size.min(items.len());
+    for (i, item) in items.iter().take(shown).enumerate() {
         history_menu_button.add_emit(
-            &format!("&{}  {c}", MENU_CHARS[base + i]),
+            &format!(
+                "&{}  {}",
+                MENU_CHARS[base + i],
+                util::menu_text(&item.c.to_string())
+            ),
+            Shortcut::None,
+            if i + 1 == shown {
+                MenuFlag::MenuDivider
+            } else {
+                MenuFlag::Normal
+            },
+            sender,
+            Action::AddChar(item.c),
+        );
+    }
+    history_menu_button.add_emit(
+        &format!("&0  All History ({})…", items.len()),
+        Shortcut::None,
+        MenuFlag::Normal,
+        sender,
+        Action::HistoryPanel,
+    );
+}
+
+// Each group is a submenu (groups with no characters aren't shown);
+// characters beyond the last menu character get no accelerator
+pub(crate) fn populate_favorites_menu_button(
+    favorites_menu_button: &mut MenuButton,
+    sender: Sender<Action>,
+) {
+    favorites_menu_button.clear();
+    favorites_menu_button.add_emit(
+        "&Manage Favorites…",
+        Shortcut::None,
+        MenuFlag::MenuDivider,
+        sender,
+        Action::Favorites,
+    );
+    let config = CONFIG.get().read().unwrap();
+    for (name, chars) in config.favorites.iter() {
+        let name = util::label_text(name);
+        for (i, c) in chars.iter().enumerate() {
+            let c_text = util::menu_text(&c.to_string());
+            let label = match MENU_CHARS.get(i) {
+                Some(key) => format!("{name}/&{key}  {c_text}"),
+                None => format!("{name}/    {c_text}"),
+            };
+            favorites_menu_button.add_emit(
+                &label,
+                Shortcut::None,
+                MenuFlag::Normal,
+                sender,
+                Action::AddChar(*c),
+            );
+        }
+    }
+}
+
+// Each snippet's label is its name followed by its text (with invisible
+// characters escaped) if that is different
+pub(crate) fn populate_snippets_menu_button(
+    snippets_menu_button: &mut MenuButton,
+    sender: Sender<Action>,
+) {
+    snippets_menu_button.clear();
+    let config = CONFIG.get().read().unwrap();
+    for (i, (name, text)) in config.snippets.iter().enumerate() {
+        let mut label = name.clone();
+        let shown = escape::encode_snippet(text);
+        if shown != *name {
+            let preview = shown.chars().take(SNIPPET_PREVIEW_MAX);
+            label.push_str(&format!(": {}", preview.collect::<String>()));
+            if shown.chars().count() > SNIPPET_PREVIEW_MAX {
+                label.push('…');
+            }
+        }
+        let label = util::menu_text(&label);
+        snippets_menu_button.add_emit(
+            &match MENU_CHARS.get(i) {
+                Some(key) => format!("&{key}  {label}"),
+                None => format!("    {label}"),
+            },
+            Shortcut::None,
+            if i + 1 == config.snippets.len() {
+                MenuFlag::MenuDivider
+            } else {
+                MenuFlag::Normal
+            },
+            sender,
+            Action::InsertSnippet(i),
+        );
+    }
+    snippets_menu_button.add_emit(
+        "&0  Save Output Editor's Text as Snippet…",
+        Shortcut::None,
+        MenuFlag::Normal,
+        sender,
+        Action::SaveSnippet,
+    );
+    for (i, (name, _)) in config.snippets.iter().enumerate() {
+        snippets_menu_button.add_emit(
+            &format!("&-  Delete Snippet/{}", util::menu_text(name)),
             Shortcut::None,
             MenuFlag::Normal,
             sender,
-            Action::AddChar(*c),
+            Action::DeleteSnippet(i),
         );
     }
 }
@@ -289,22 +649,114 @@ pub fn add_event_handlers(
             sender.send(Action::Quit);
         }
     });
+    // The focused widget gets each key first; any it doesn't use come
+    // here as shortcuts
+    let keys = {
+        let config = CONFIG.get().read().unwrap();
+        config.keys.clone()
+    };
     main_window.handle(move |_, event| match event {
-        Event::KeyUp => match app::event_key() {
-            Key::Help | Key::F1 => {
-                sender.send(Action::Help);
+        Event::Shortcut => match keys.action_for(
+            Scope::Window,
+            app::event_key(),
+            app::event_state(),
+        ) {
+            Some(action) => {
+                sender.send(action);
                 true
             }
-            Key::F2 => {
-                sender.send(Action::PopupSearches);
-                true
+            None => false,
+        },
+        _ => false,
+    });
+}
+
+// Records the output editor's state before typing, cutting, or pasting
+// changes it, and handles the output editor's key bindings (undo, redo,
+// and code point toggling) and, while completions are shown, the keys for
+// choosing one
+pub fn add_output_event_handler(
+    output_editor: &mut TextEditor,
+    mut completion_browser: HoldBrowser,
+    sender: Sender<Action>,
+    history: Rc<RefCell<EditHistory>>,
+) {
+    let keys = {
+        let config = CONFIG.get().read().unwrap();
+        config.keys.clone()
+    };
+    output_editor.handle(move |output_editor, event| match event {
+        Event::KeyDown => {
+            let state = app::event_state();
+            let ctrl = state.contains(EventState::Ctrl);
+            let key = app::event_key();
+            if let Some(action) =
+                keys.action_for(Scope::OutputEditor, key, state)
+            {
+                sender.send(action);
+                return true;
             }
-            Key::F3 => {
-                sender.send(Action::FocusToSearchResults);
-                true
+            if completion_browser.visible()
+                && !ctrl
+                && !state.contains(EventState::Alt)
+                && !state.contains(EventState::Shift)
+                && completion_key(&mut completion_browser, key, sender)
+            {
+                return true;
             }
-            _ => false,
-        },
+            if ctrl && key == Key::from_char('x') {
+                history.borrow_mut().record(EditState::of(output_editor));
+            } else if key == Key::BackSpace
+                || key == Key::Delete
+                || (!ctrl
+                    && !state.contains(EventState::Alt)
+                    && !app::event_text().is_empty())
+            {
+                history
+                    .borrow_mut()
+                    .record_typing(EditState::of(output_editor));
+            }
+            false
+        }
+        Event::Paste => {
+            history.borrow_mut().record(EditState::of(output_editor));
+            false
+        }
+        Event::Push | Event::Unfocus => {
+            sender.send(Action::HideCompletion);
+            false
+        }
         _ => false,
     });
 }
+
+// Tab or Enter chooses the selected completion, Up and Down change the
+// selection, and Esc hides them; returns true if the key was used. Keys
+// that move the cursor hide them but are still used by the editor
+fn completion_key(
+    completion_browser: &mut HoldBrowser,
+    key: Key,
+    sender: Sender<Action>,
+) -> bool {
+    if key == Key::Tab || key == Key::Enter || key == Key::KPEnter {
+        sender.send(Action::Complete);
+    } else if key == Key::Up || key == Key::Down {
+        let line = completion_browser.value()
+            + if key == Key::Up { -1 } else { 1 };
+        completion_browser.select(line.clamp(1, completion_browser.size()));
+    } else if key == Key::Escape {
+        completion_browser.hide();
+    } else {
+        if key == Key::Left
+            || key == Key::Right
+            || key == Key::Home
+            || key == Key::End
+            || key == Key::PageUp
+            || key == Key::PageDown
+        {
+            completion_browser.hide();
+        }
+        return false;
+    }
+    true
+}
@@ -2,18 +2,24 @@
 // License: GPLv3
 
 use super::CONFIG;
+use crate::config::{default_history, default_searches};
 use crate::fixed::{
-    APPNAME, AUTO_MENU_SIZE, BUTTON_HEIGHT, BUTTON_WIDTH, ICON, PAD,
-    SCALE_MAX, SCALE_MIN,
+    APPNAME, AUTO_MENU_SIZE, BUTTON_HEIGHT, BUTTON_WIDTH,
+    COMPLETION_PREFIX_DEFAULT, ICON, PAD, SCALE_MAX, SCALE_MIN,
 };
+use crate::history::{History, HistoryItem, HistoryOrder};
+use crate::list_editor::{Entry, ListEditor};
+use crate::theme::Theme;
 use crate::util;
 use fltk::{
     app,
-    button::Button,
+    button::{Button, CheckButton},
     enums::{Align, FrameType},
     frame::Frame,
-    group::Flex,
+    group::{Flex, Tabs},
     image::SvgImage,
+    input::Input,
+    menu::Choice,
     misc::Spinner,
     prelude::*,
     window::Window,
@@ -30,19 +36,25 @@ impl Form {
     pub fn default() -> Self {
         let ok = Rc::from(RefCell::from(false));
         let mut form = make_form();
-        let mut vbox = Flex::default().size_of_parent().column();
-        vbox.set_margin(PAD);
-        vbox.set_pad(PAD);
+        let tabs = Tabs::new(PAD, PAD, WIDTH - PAD * 2, TABS_HEIGHT, None);
+        let general_tab = make_tab("General");
         make_config_row();
         let spinners = make_spinners();
-        let (button_row, mut buttons) = make_buttons();
-        vbox.set_size(&button_row, BUTTON_HEIGHT);
-        vbox.end();
+        general_tab.end();
+        let searches_tab = make_tab("Searches");
+        let searches_editor = make_searches_editor();
+        searches_tab.end();
+        let history_tab = make_tab("History");
+        let history_editor = make_history_editor();
+        history_tab.end();
+        tabs.end();
+        let mut buttons = make_buttons();
         form.end();
         form.make_modal(true);
         add_event_handlers(
             &mut form,
             &spinners,
+            Editors { searches_editor, history_editor },
             &mut buttons,
             Rc::clone(&ok),
         );
@@ -63,7 +75,17 @@ impl Drop for Form {
 struct Spinners {
     pub searches_size_spinner: Spinner,
     pub history_size_spinner: Spinner,
+    pub history_order_choice: Choice,
     pub scale_spinner: Spinner,
+    pub theme_choice: Choice,
+    pub copy_as_default_check: CheckButton,
+    pub completion_prefix_input: Input,
+    pub lock_file_check: CheckButton,
+}
+
+struct Editors {
+    pub searches_editor: ListEditor,
+    pub history_editor: ListEditor,
 }
 
 struct Buttons {
@@ -74,7 +96,7 @@ struct Buttons {
 fn make_form() -> Window {
     let image = SvgImage::from_data(ICON).unwrap();
     let mut form = Window::default()
-        .with_size(WIDTH, 180)
+        .with_size(WIDTH, HEIGHT)
         .with_label(&format!("Options — {APPNAME}"));
     if let Some(window) = app::first_window() {
         form.set_pos(window.x() + 50, window.y() + 100);
@@ -83,6 +105,20 @@ fn make_form() -> Window {
     form
 }
 
+fn make_tab(label: &'static str) -> Flex {
+    let mut tab = Flex::new(
+        PAD,
+        PAD + TAB_HEIGHT,
+        WIDTH - PAD * 2,
+        TABS_HEIGHT - TAB_HEIGHT,
+        label,
+    )
+    .column();
+    tab.set_margin(PAD);
+    tab.set_pad(PAD);
+    tab
+}
+
 fn make_config_row() {
     let mut row = Flex::default().row();
     let label = Frame::default()
@@ -110,11 +146,12 @@ fn make_spinners() -> Spinners {
     let history_size_spinner = make_row(
         "&History Size",
         config.history_size as f64,
-        &format!("The maximum number of characters to keep in the history menu (default {AUTO_MENU_SIZE})"),
+        &format!("The maximum number of characters to show in the history menu; the rest are in the history panel (default {AUTO_MENU_SIZE})"),
         2.0,
         AUTO_MENU_SIZE as f64,
         1.0,
     );
+    let history_order_choice = make_history_order_row(config.history_order);
     let scale_spinner = make_row(
         "Sca&le",
         config.window_scale as f64,
@@ -123,7 +160,166 @@ fn make_spinners() -> Spinners {
         SCALE_MAX as f64,
         0.1,
     );
-    Spinners { searches_size_spinner, history_size_spinner, scale_spinner }
+    let theme_choice = make_theme_row(config.theme);
+    let completion_prefix_input =
+        make_completion_prefix_row(&config.completion_prefix);
+    let mut copy_as_default_check = CheckButton::default()
+        .with_label("Copy Copies in the Last Cop&y As Format");
+    copy_as_default_check.set_checked(config.copy_as_default);
+    copy_as_default_check.set_tooltip(
+        "If unchecked, the Copy button always copies the text as it is",
+    );
+    let mut lock_file_check = CheckButton::default()
+        .with_label("Loc&k the Configuration File When Saving");
+    lock_file_check.set_checked(config.lock_file);
+    lock_file_check.set_tooltip("Saving merges with the changes other running instances have saved; locking stops two instances saving at the same time (uncheck if the file's folder doesn't allow lock files)");
+    Spinners {
+        searches_size_spinner,
+        history_size_spinner,
+        history_order_choice,
+        scale_spinner,
+        theme_choice,
+        copy_as_default_check,
+        completion_prefix_input,
+        lock_file_check,
+    }
+}
+
+fn make_completion_prefix_row(prefix: &str) -> Input {
+    let mut row = Flex::default().row();
+    row.set_pad(PAD);
+    let mut label = Button::default()
+        .with_label("Com&pletion Prefix")
+        .with_align(Align::Inside | Align::Left);
+    label.set_frame(FrameType::NoBox);
+    label.clear_visible_focus();
+    let mut input = Input::default();
+    input.set_value(prefix);
+    input.set_maximum_size(PREFIX_MAX);
+    input.set_tooltip(&format!("Typing this followed by words, e.g., {COMPLETION_PREFIX_DEFAULT}rarr or {COMPLETION_PREFIX_DEFAULT}right_arrow, at the start of a word in the output editor pops up the matching characters as you type; Tab or Enter replaces the word with the selected one (default {COMPLETION_PREFIX_DEFAULT}; empty for none)"));
+    row.end();
+    label.set_callback({
+        let mut input = input.clone();
+        move |_| {
+            input.take_focus().unwrap_or_default();
+        }
+    });
+    input
+}
+
+fn make_theme_row(theme: Theme) -> Choice {
+    let mut row = Flex::default().row();
+    row.set_pad(PAD);
+    let mut label = Button::default()
+        .with_label("&Theme")
+        .with_align(Align::Inside | Align::Left);
+    label.set_frame(FrameType::NoBox);
+    label.clear_visible_focus();
+    let mut theme_choice = Choice::default();
+    for (i, t) in Theme::ALL.iter().enumerate() {
+        theme_choice.add_choice(t.label());
+        if *t == theme {
+            theme_choice.set_value(i as i32);
+        }
+    }
+    theme_choice.set_tooltip("The user interface colors (default Light)");
+    row.end();
+    label.set_callback({
+        let mut theme_choice = theme_choice.clone();
+        move |_| {
+            theme_choice.take_focus().unwrap_or_default();
+        }
+    });
+    theme_choice
+}
+
+fn make_history_order_row(history_order: HistoryOrder) -> Choice {
+    let mut row = Flex::default().row();
+    row.set_pad(PAD);
+    let mut label = Button::default()
+        .with_label("History O&rder")
+        .with_align(Align::Inside | Align::Left);
+    label.set_frame(FrameType::NoBox);
+    label.clear_visible_focus();
+    let mut history_order_choice = Choice::default();
+    for (i, order) in HistoryOrder::ALL.iter().enumerate() {
+        history_order_choice.add_choice(order.label());
+        if *order == history_order {
+            history_order_choice.set_value(i as i32);
+        }
+    }
+    history_order_choice.set_tooltip(
+        "The order of the characters in the history menu and panel (default Most Recently Used)",
+    );
+    row.end();
+    label.set_callback({
+        let mut history_order_choice = history_order_choice.clone();
+        move |_| {
+            history_order_choice.take_focus().unwrap_or_default();
+        }
+    });
+    history_order_choice
+}
+
+fn make_searches_editor() -> ListEditor {
+    let config = CONFIG.get().read().unwrap();
+    let pinned = config.searches_pinned;
+    let entries = config
+        .searches
+        .iter()
+        .enumerate()
+        .map(|(i, search)| Entry {
+            text: search.to_string(),
+            pinned: i < pinned,
+            original: Some(i),
+        })
+        .collect();
+    let defaults = default_searches()
+        .iter()
+        .map(|search| Entry::new(search))
+        .collect();
+    ListEditor::new(
+        entries,
+        defaults,
+        |text| text.to_string(),
+        |text| {
+            let text = text.trim();
+            if text.is_empty() {
+                None
+            } else {
+                Some(text.to_string())
+            }
+        },
+    )
+}
+
+fn make_history_editor() -> ListEditor {
+    let config = CONFIG.get().read().unwrap();
+    let entries = config
+        .history
+        .items()
+        .iter()
+        .enumerate()
+        .map(|(i, item)| Entry {
+            text: item.c.to_string(),
+            pinned: item.pinned,
+            original: Some(i),
+        })
+        .collect();
+    let defaults = default_history()
+        .items()
+        .iter()
+        .map(|item| Entry::new(&item.c.to_string()))
+        .collect();
+    ListEditor::new(
+        entries,
+        defaults,
+        |text| match text.chars().next() {
+            Some(c) => format!("{text}    U+{:04X}", c as u32),
+            None => String::new(),
+        },
+        |text| text.chars().find(|c| !c.is_whitespace()).map(String::from),
+    )
 }
 
 fn make_row(
@@ -157,8 +353,15 @@ fn make_row(
     spinner
 }
 
-fn make_buttons() -> (Flex, Buttons) {
-    let mut row = Flex::default().size_of_parent().row();
+fn make_buttons() -> Buttons {
+    let mut row = Flex::new(
+        PAD,
+        HEIGHT - PAD - BUTTON_HEIGHT,
+        WIDTH - PAD * 2,
+        BUTTON_HEIGHT,
+        None,
+    )
+    .row();
     row.set_pad(PAD);
     Frame::default(); // pad left of buttons
     let ok_button = Button::default().with_label("&OK");
@@ -167,19 +370,26 @@ fn make_buttons() -> (Flex, Buttons) {
     row.set_size(&ok_button, BUTTON_WIDTH);
     row.set_size(&cancel_button, BUTTON_WIDTH);
     row.end();
-    (row, Buttons { ok_button, cancel_button })
+    Buttons { ok_button, cancel_button }
 }
 
 fn add_event_handlers(
     form: &mut Window,
     spinners: &Spinners,
+    editors: Editors,
     buttons: &mut Buttons,
     ok: Rc<RefCell<bool>>,
 ) {
     buttons.ok_button.set_callback({
         let searches_size_spinner = spinners.searches_size_spinner.clone();
         let history_size_spinner = spinners.history_size_spinner.clone();
+        let history_order_choice = spinners.history_order_choice.clone();
         let scale_spinner = spinners.scale_spinner.clone();
+        let theme_choice = spinners.theme_choice.clone();
+        let copy_as_default_check = spinners.copy_as_default_check.clone();
+        let completion_prefix_input =
+            spinners.completion_prefix_input.clone();
+        let lock_file_check = spinners.lock_file_check.clone();
         let mut form = form.clone();
         move |_| {
             *ok.borrow_mut() = true;
@@ -192,6 +402,33 @@ fn add_event_handlers(
             }
             config.searches_size = searches_size_spinner.value() as usize;
             config.history_size = history_size_spinner.value() as usize;
+            if let Some(order) =
+                HistoryOrder::ALL.get(history_order_choice.value() as usize)
+            {
+                config.history_order = *order;
+            }
+            if let Some(theme) =
+                Theme::ALL.get(theme_choice.value() as usize)
+            {
+                config.theme = *theme;
+            }
+            config.copy_as_default = copy_as_default_check.is_checked();
+            config.lock_file = lock_file_check.is_checked();
+            // Letters and digits would be taken as part of the token
+            config.completion_prefix = completion_prefix_input
+                .value()
+                .chars()
+                .filter(|c| !c.is_alphanumeric() && !c.is_whitespace())
+                .collect();
+            let entries = editors.searches_editor.entries();
+            config.searches_pinned =
+                entries.iter().filter(|entry| entry.pinned).count();
+            config.searches =
+                entries.into_iter().map(|entry| entry.text).collect();
+            config.history = edited_history(
+                &config.history,
+                &editors.history_editor.entries(),
+            );
             form.hide();
         }
     });
@@ -203,4 +440,23 @@ fn add_event_handlers(
     });
 }
 
-const WIDTH: i32 = 320;
+// Edited and new entries start afresh; the others keep their use counts
+fn edited_history(history: &History, entries: &[Entry]) -> History {
+    let mut edited = History::default();
+    for entry in entries.iter() {
+        let item = entry.original.and_then(|i| history.items().get(i));
+        if let Some(c) = entry.text.chars().next() {
+            let mut item =
+                item.copied().unwrap_or_else(|| HistoryItem::new(c));
+            item.pinned = entry.pinned;
+            edited.push(item);
+        }
+    }
+    edited
+}
+
+const WIDTH: i32 = 480;
+const HEIGHT: i32 = 400;
+const TAB_HEIGHT: i32 = 25;
+const TABS_HEIGHT: i32 = HEIGHT - BUTTON_HEIGHT - PAD * 3;
+const PREFIX_MAX: i32 = 4;
@@ -0,0 +1,190 @@
+// Copyright © 2021-22 Mark Summerfield. All rights reserved.
+// License: GPLv3
+
+use fltk::app::{self, Scheme};
+use std::process::Command;
+
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum Theme {
+    #[default]
+    Light,
+    Dark,
+    HighContrast,
+    System,
+}
+
+impl Theme {
+    pub const ALL: [Theme; 4] =
+        [Theme::Light, Theme::Dark, Theme::HighContrast, Theme::System];
+
+    // The name used in the configuration file
+    pub fn name(&self) -> &'static str {
+        match self {
+            Theme::Light => "light",
+            Theme::Dark => "dark",
+            Theme::HighContrast => "high-contrast",
+            Theme::System => "system",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Theme> {
+        Theme::ALL.iter().find(|theme| theme.name() == name).copied()
+    }
+
+    // The name shown to the user
+    pub fn label(&self) -> &'static str {
+        match self {
+            Theme::Light => "Light",
+            Theme::Dark => "Dark",
+            Theme::HighContrast => "High Contrast",
+            Theme::System => "Follow System",
+        }
+    }
+
+    pub fn colors(&self) -> Colors {
+        match self {
+            Theme::Light => LIGHT,
+            Theme::Dark => DARK,
+            Theme::HighContrast => HIGH_CONTRAST,
+            Theme::System => {
+                if system_is_dark() {
+                    DARK
+                } else {
+                    LIGHT
+                }
+            }
+        }
+    }
+}
+
+type Rgb = (u8, u8, u8);
+
+// The browser colors are FLTK colormap indexes for use in @B and @C
+// format codes
+#[derive(Copy, Clone, Debug)]
+pub struct Colors {
+    pub scheme: Scheme,
+    pub background: Rgb,
+    pub background2: Rgb,
+    pub foreground: Rgb,
+    pub selection: Rgb,
+    pub stripe_bg: u8,
+    pub header_fg: u8,
+    pub header_bg: u8,
+    pub no_match_fg: u8,
+    pub no_match_bg: u8,
+    pub missing_fg: u8,
+    html_colors: &'static [(&'static str, &'static str)],
+}
+
+impl Colors {
+    pub fn apply(&self) {
+        app::set_scheme(self.scheme);
+        let (r, g, b) = self.background;
+        app::set_background_color(r, g, b);
+        let (r, g, b) = self.background2;
+        app::set_background2_color(r, g, b);
+        let (r, g, b) = self.foreground;
+        app::set_foreground_color(r, g, b);
+        let (r, g, b) = self.selection;
+        app::set_selection_color(r, g, b);
+        app::redraw();
+    }
+
+    // Returns the HTML with the colors it uses replaced by ones that are
+    // readable against this theme's background
+    pub fn html(&self, html: &str) -> String {
+        let mut html = html.to_string();
+        for (from, to) in self.html_colors {
+            html = html.replace(from, to);
+        }
+        html
+    }
+}
+
+const LIGHT: Colors = Colors {
+    scheme: Scheme::Gleam,
+    background: (192, 192, 192),
+    background2: (255, 255, 255),
+    foreground: (0, 0, 0),
+    selection: (0, 0, 128),
+    stripe_bg: 247,
+    header_fg: 7,
+    header_bg: 58,
+    no_match_fg: 1,
+    no_match_bg: 3,
+    missing_fg: 1,
+    html_colors: &[],
+};
+
+const DARK: Colors = Colors {
+    scheme: Scheme::Gleam,
+    background: (50, 50, 50),
+    background2: (30, 30, 30),
+    foreground: (220, 220, 220),
+    selection: (70, 110, 160),
+    stripe_bg: 36,
+    header_fg: 7,
+    header_bg: 58,
+    no_match_fg: 7,
+    no_match_bg: 1,
+    missing_fg: 1,
+    html_colors: &[
+        ("color=navy", "color=#8CB4FF"),
+        ("color=blue", "color=#7FBFFF"),
+        ("color=green", "color=#8FD88F"),
+        ("color=#008000", "color=#8FD88F"),
+        ("color=#555", "color=#AAA"),
+    ],
+};
+
+const HIGH_CONTRAST: Colors = Colors {
+    scheme: Scheme::Base,
+    background: (0, 0, 0),
+    background2: (0, 0, 0),
+    foreground: (255, 255, 255),
+    selection: (255, 255, 0),
+    stripe_bg: 34,
+    header_fg: 0,
+    header_bg: 3,
+    no_match_fg: 7,
+    no_match_bg: 1,
+    missing_fg: 1,
+    html_colors: &[
+        ("color=navy", "color=#FFFF00"),
+        ("color=blue", "color=#00FFFF"),
+        ("color=green", "color=#00FF00"),
+        ("color=#008000", "color=#00FF00"),
+        ("color=#555", "color=#FFFFFF"),
+    ],
+};
+
+// Best effort: if the desktop's preference can't be found, assume light
+fn system_is_dark() -> bool {
+    let (program, args, dark): (&str, &[&str], &str) = if cfg!(windows) {
+        (
+            "reg",
+            &[
+                "query",
+                "HKCU\\Software\\Microsoft\\Windows\\CurrentVersion\\Themes\\Personalize",
+                "/v",
+                "AppsUseLightTheme",
+            ],
+            "0x0",
+        )
+    } else if cfg!(target_os = "macos") {
+        ("defaults", &["read", "-g", "AppleInterfaceStyle"], "Dark")
+    } else {
+        (
+            "gsettings",
+            &["get", "org.gnome.desktop.interface", "color-scheme"],
+            "dark",
+        )
+    };
+    match Command::new(program).args(args).output() {
+        Ok(output) => {
+            String::from_utf8_lossy(&output.stdout).contains(dark)
+        }
+        Err(_) => false,
+    }
+}
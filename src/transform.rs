@@ -0,0 +1,140 @@
+// Copyright © 2021-22 Mark Summerfield. All rights reserved.
+// License: GPLv3
+
+use unicode_normalization::{
+    char::is_combining_mark, is_nfc, UnicodeNormalization,
+};
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Transform {
+    Nfc,
+    Nfd,
+    Nfkc,
+    Nfkd,
+    Upper,
+    Lower,
+    Title,
+    Fold,
+}
+
+impl Transform {
+    pub const ALL: [Transform; 8] = [
+        Transform::Nfc,
+        Transform::Nfd,
+        Transform::Nfkc,
+        Transform::Nfkd,
+        Transform::Upper,
+        Transform::Lower,
+        Transform::Title,
+        Transform::Fold,
+    ];
+
+    // The text used in menus (with an accelerator)
+    pub fn label(&self) -> &'static str {
+        match self {
+            Transform::Nfc => "NF&C (Canonical Composition)",
+            Transform::Nfd => "NF&D (Canonical Decomposition)",
+            Transform::Nfkc => "NFKC (Compatibility Co&mposition)",
+            Transform::Nfkd => "NFKD (Compatibility Decom&position)",
+            Transform::Upper => "&Uppercase",
+            Transform::Lower => "&Lowercase",
+            Transform::Title => "&Title Case",
+            Transform::Fold => "Case &Fold",
+        }
+    }
+
+    pub fn apply(&self, text: &str) -> String {
+        match self {
+            Transform::Nfc => text.nfc().collect(),
+            Transform::Nfd => text.nfd().collect(),
+            Transform::Nfkc => text.nfkc().collect(),
+            Transform::Nfkd => text.nfkd().collect(),
+            Transform::Upper => text.to_uppercase(),
+            Transform::Lower => text.to_lowercase(),
+            Transform::Title => title_case(text),
+            Transform::Fold => caseless::default_case_fold_str(text),
+        }
+    }
+}
+
+pub fn is_normalized(text: &str) -> bool {
+    is_nfc(text)
+}
+
+// A word is a run of letters and digits (with any combining marks and
+// apostrophes that follow them, so "don't" becomes "Don't"); its first
+// character is uppercased and the rest lowercased (so that, e.g., a final
+// sigma is kept and ﬁ becomes Fi)
+fn title_case(text: &str) -> String {
+    let mut title = String::with_capacity(text.len());
+    let mut word = String::new();
+    for c in text.chars() {
+        if c.is_alphanumeric()
+            || (!word.is_empty()
+                && (is_combining_mark(c) || c == '\'' || c == '’'))
+        {
+            word.push(c);
+        } else {
+            title.push_str(&title_word(&word));
+            word.clear();
+            title.push(c);
+        }
+    }
+    title.push_str(&title_word(&word));
+    title
+}
+
+fn title_word(word: &str) -> String {
+    let mut chars = word.chars();
+    match chars.next() {
+        Some(first) => {
+            let mut upper = first.to_uppercase();
+            let mut title = String::with_capacity(word.len());
+            title.extend(upper.next());
+            let rest = upper.collect::<String>() + chars.as_str();
+            title.push_str(&rest.to_lowercase());
+            title
+        }
+        None => String::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn normalization_forms() {
+        let composed = "caf\u{E9}";
+        let decomposed = "cafe\u{301}";
+        assert_eq!(Transform::Nfc.apply(decomposed), composed);
+        assert_eq!(Transform::Nfd.apply(composed), decomposed);
+        assert_eq!(Transform::Nfkc.apply("\u{FB01}x\u{B2}"), "fix2");
+        assert_eq!(Transform::Nfkd.apply("\u{2460}"), "1");
+        assert!(is_normalized(composed));
+        assert!(!is_normalized(decomposed));
+    }
+
+    #[test]
+    fn title_case_words() {
+        let title = |text| Transform::Title.apply(text);
+        assert_eq!(title("don't STOP me-now"), "Don't Stop Me-Now");
+        assert_eq!(title("\u{FB01}sh"), "Fish");
+        assert_eq!(title("ΟΔΟΣ ΟΔΟΣ"), "Οδος Οδος");
+        assert_eq!(title("e\u{301}cole 2nd"), "E\u{301}cole 2nd");
+    }
+
+    #[test]
+    fn case_fold() {
+        let fold = |text| Transform::Fold.apply(text);
+        assert_eq!(fold("Straße STRAẞE"), "strasse strasse");
+        assert_eq!(fold("\u{FB01}"), "fi");
+        assert_eq!(fold("ΟΔΟΣ οδος"), "οδοσ οδοσ");
+        assert_eq!(fold("MiXeD"), "mixed");
+        // Cherokee folds to uppercase
+        assert_eq!(
+            fold("\u{13A0}\u{AB70}\u{13F8}"),
+            "\u{13A0}\u{13A0}\u{13F0}"
+        );
+    }
+}
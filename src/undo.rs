@@ -0,0 +1,97 @@
+// Copyright © 2021-22 Mark Summerfield. All rights reserved.
+// License: GPLv3
+
+use crate::fixed::UNDO_MAX;
+use fltk::{prelude::*, text::TextEditor};
+
+// The output editor's text with its cursor position and selection mark
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct EditState {
+    pub text: String,
+    pub position: i32,
+    pub mark: i32,
+}
+
+impl EditState {
+    pub fn of(editor: &TextEditor) -> Self {
+        let position = editor.insert_position();
+        match editor.buffer() {
+            Some(buffer) => {
+                let mark = match buffer.selection_position() {
+                    Some((start, end)) if start == position => end,
+                    Some((start, _)) => start,
+                    None => position,
+                };
+                Self { text: buffer.text(), position, mark }
+            }
+            None => Self::default(),
+        }
+    }
+
+    pub fn restore(&self, editor: &mut TextEditor) {
+        if let Some(mut buffer) = editor.buffer() {
+            buffer.set_text(&self.text);
+            if self.mark == self.position {
+                buffer.unselect();
+            } else {
+                buffer.select(
+                    self.position.min(self.mark),
+                    self.position.max(self.mark),
+                );
+            }
+            editor.set_insert_position(self.position);
+            editor.show_insert_position();
+        }
+    }
+}
+
+// Undo and redo stacks of output editor states. A run of typing is
+// recorded as a single state so that it is undone in one go.
+#[derive(Clone, Debug, Default)]
+pub struct EditHistory {
+    undo: Vec<EditState>,
+    redo: Vec<EditState>,
+    typing: bool,
+}
+
+impl EditHistory {
+    // Call with the state from before a change
+    pub fn record(&mut self, state: EditState) {
+        self.typing = false;
+        self.push(state);
+    }
+
+    // Call with the state from before a keystroke that changes the text
+    pub fn record_typing(&mut self, state: EditState) {
+        if !self.typing {
+            self.push(state);
+            self.typing = true;
+        }
+    }
+
+    fn push(&mut self, state: EditState) {
+        if self.undo.last() != Some(&state) {
+            self.undo.push(state);
+            if self.undo.len() > UNDO_MAX {
+                self.undo.remove(0);
+            }
+        }
+        self.redo.clear();
+    }
+
+    // Returns the state to restore; current is the state being left
+    pub fn undo(&mut self, current: EditState) -> Option<EditState> {
+        let state = self.undo.pop()?;
+        self.redo.push(current);
+        self.typing = false;
+        Some(state)
+    }
+
+    // Returns the state to restore; current is the state being left
+    pub fn redo(&mut self, current: EditState) -> Option<EditState> {
+        let state = self.redo.pop()?;
+        self.undo.push(current);
+        self.typing = false;
+        Some(state)
+    }
+}
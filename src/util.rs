@@ -8,7 +8,7 @@ use fltk::{
     prelude::*,
 };
 use levenshtein::levenshtein;
-use std::{cmp, fmt, str};
+use std::{cmp, collections::VecDeque, fmt, str};
 
 pub fn x() -> i32 {
     (app::screen_size().0 / 2.0) as i32
@@ -26,14 +26,17 @@ pub fn capitalize_first(s: &str) -> String {
     }
 }
 
-// Returns a number converted from the given str or the default
-pub fn get_num<T>(s: &str, minimum: T, maximum: T, default: T) -> T
+// Returns a number converted from the given str or why it isn't valid
+pub fn get_num<T>(s: &str, minimum: T, maximum: T) -> Result<T, String>
 where
-    T: num::Num + cmp::PartialOrd + Copy + str::FromStr,
+    T: num::Num + cmp::PartialOrd + Copy + str::FromStr + fmt::Display,
 {
-    match s.parse() {
-        Ok(n) if minimum <= n && n <= maximum => n,
-        _ => default,
+    match s.trim().parse() {
+        Ok(n) if minimum <= n && n <= maximum => Ok(n),
+        Ok(n) => {
+            Err(format!("{n} is outside the range {minimum}–{maximum}"))
+        }
+        Err(_) => Err(format!("“{s}” isn't a number")),
     }
 }
 
@@ -77,6 +80,56 @@ impl std::fmt::Display for Pos {
     }
 }
 
+// Returns the text with the characters FLTK treats specially in labels
+// (symbols and shortcuts) doubled so that they are shown literally
+pub fn label_text(s: &str) -> String {
+    s.replace('@', "@@").replace('&', "&&")
+}
+
+// Returns where the completion token (the configured prefix followed by
+// letters, digits, '_', or '-', e.g., :rarr) at the end of the text starts
+// and the token without its prefix
+pub fn completion_token(text: &str) -> Option<(usize, &str)> {
+    let config = CONFIG.get().read().unwrap();
+    find_completion_token(text, &config.completion_prefix)
+}
+
+// The prefix only starts a token at the start of the text or after
+// whitespace so that, e.g., 10:30 and key:value aren't tokens
+fn find_completion_token<'a>(
+    text: &'a str,
+    prefix: &str,
+) -> Option<(usize, &'a str)> {
+    if prefix.is_empty() {
+        return None;
+    }
+    let size = text
+        .chars()
+        .rev()
+        .take_while(|c| c.is_alphanumeric() || *c == '_' || *c == '-')
+        .map(|c| c.len_utf8())
+        .sum::<usize>();
+    let start = text.len() - size;
+    let before = text[..start].strip_suffix(prefix)?;
+    if size > 0 && before.chars().last().is_none_or(char::is_whitespace) {
+        Some((before.len(), &text[start..]))
+    } else {
+        None
+    }
+}
+
+// Returns the text made safe for a menu item's label, i.e., with '/' (and
+// '\\') escaped so that they don't create submenus
+pub fn menu_text(s: &str) -> String {
+    label_text(s).replace('\\', "\\\\").replace('/', "\\/")
+}
+
+// Returns the name trimmed and with the characters that would split it into
+// submenus replaced, e.g., for favorites group names
+pub fn menu_name(s: &str) -> String {
+    s.replace(|c: char| c == '/' || c == '\\', "-").trim().to_string()
+}
+
 pub fn string_for_codepoint(cp: u32) -> String {
     if cp <= 0xFFFF {
         format!("  {cp:04X}")
@@ -85,44 +138,41 @@ pub fn string_for_codepoint(cp: u32) -> String {
     }
 }
 
-pub fn add_to_history(c: char) -> bool {
-    {
-        let config = CONFIG.get().read().unwrap();
-        if config.history.contains(&c) {
-            return false;
-        }
-    }
-    let mut config = CONFIG.get().write().unwrap();
-    config.history.push_front(c);
-    config.history.truncate(AUTO_MENU_SIZE);
-    true
+pub fn add_to_history(c: char) {
+    CONFIG.get().write().unwrap().history.add(c);
 }
 
 pub fn add_to_searches(s: &str) -> bool {
     let mut config = CONFIG.get().write().unwrap();
-    if let Some(front) = config.searches.front() {
+    let pinned = config.searches_pinned;
+    merge_search(&mut config.searches, pinned, s)
+}
+
+// The pinned searches stay at the front in the order the user chose, so
+// "front" here means the first unpinned search
+pub fn merge_search(
+    searches: &mut VecDeque<String>,
+    pinned: usize,
+    s: &str,
+) -> bool {
+    let pinned = pinned.min(searches.len());
+    if searches.iter().take(pinned).any(|t| t == s) {
+        return false; // The new string is pinned
+    }
+    if let Some(front) = searches.get(pinned) {
         if front == s {
             return false; // The new string is already the first one
         }
     }
     // If the same as an existing one, move the existing one to the front
-    let mut found = false;
-    let mut i = 0;
-    for t in config.searches.iter() {
-        if t == s {
-            found = true;
-            break;
-        }
-        i += 1;
-    }
     let s = s.to_string();
-    if found {
-        config.searches.remove(i);
-        config.searches.push_front(s);
+    if let Some(i) = searches.iter().skip(pinned).position(|t| *t == s) {
+        searches.remove(pinned + i);
+        searches.insert(pinned, s);
         return true;
     }
     // If the first one is almost the same as the new one replace with new
-    if let Some(front) = config.searches.front_mut() {
+    if let Some(front) = searches.get_mut(pinned) {
         if s.starts_with(front.as_str())
             || levenshtein(&s, front.as_str()) < 2
         {
@@ -130,8 +180,8 @@ pub fn add_to_searches(s: &str) -> bool {
             return true;
         }
     }
-    config.searches.push_front(s);
-    config.searches.truncate(AUTO_MENU_SIZE);
+    searches.insert(pinned, s);
+    searches.truncate(AUTO_MENU_SIZE.max(pinned));
     true
 }
 
@@ -156,3 +206,33 @@ pub fn populate_find_combo(
         );
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn completion_token_after_whitespace() {
+        assert_eq!(find_completion_token(":rarr", ":"), Some((0, "rarr")));
+        assert_eq!(
+            find_completion_token("a →\n:right_arrow", ":"),
+            Some((6, "right_arrow"))
+        );
+        assert_eq!(
+            find_completion_token("x ;;em-dash", ";;"),
+            Some((2, "em-dash"))
+        );
+        assert_eq!(find_completion_token("café :é", ":"), Some((6, "é")));
+    }
+
+    #[test]
+    fn completion_token_needs_a_word_start() {
+        for text in ["meet at 10:30", "key:value", "a:b", "(:rarr"] {
+            assert_eq!(find_completion_token(text, ":"), None, "{text}");
+        }
+        assert_eq!(find_completion_token(":", ":"), None);
+        assert_eq!(find_completion_token(":rarr ", ":"), None);
+        assert_eq!(find_completion_token("rarr", ":"), None);
+        assert_eq!(find_completion_token(":rarr", ""), None);
+    }
+}